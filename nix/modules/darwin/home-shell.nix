@@ -152,28 +152,8 @@
         export EDITOR="nvim"
       fi
 
-      # --- Zellij session helpers ---
-      _zj() {
-        local layout="$1"
-        local existing=$(zellij list-sessions 2>/dev/null | sed 's/\x1b\[[0-9;]*m//g' | grep "^$layout-" | grep -v "EXITED" | awk '{print $1}' | head -1)
-        if [[ -n "$existing" ]]; then
-          zellij pipe --plugin "file:$HOME/dev/misc/dotfiles/zellij/.config/zellij/plugins/zellij-switch.wasm" -- "-s $existing"
-        else
-          zellij pipe --plugin "file:$HOME/dev/misc/dotfiles/zellij/.config/zellij/plugins/zellij-switch.wasm" -- "-s $layout-$(date +%Y%m%d-%H%M%S) -l $layout"
-        fi
-      }
-      zeb() { _zj energyboard; }
-      zbo() { _zj backoffice; }
-      zea() { _zj easyasset; }
-      zex() { _zj elixir; }
-      zgs() { _zj gappel-solutions; }
-      zdc() { _zj decon; }
-      zsc() { _zj screensaver; }
-      zlc() { _zj lazychat; }
-      zco() { _zj colony; }
-      zms() { _zj msp; }
-      zsf() { _zj smartflex; }
-      zll() { _zj lazylink; }
+      # --- Zellij session helpers (aliases from the zellij-welcome catalog) ---
+      eval "$(zellij-welcome shell-init zsh)"
 
       function y() {
         local tmp="$(mktemp -t "yazi-cwd.XXXXXX")"
@@ -0,0 +1,407 @@
+//! Actions on the running instances of the highlighted entry: kill (after
+//! confirmation), force-delete an exited session, rename and detach the
+//! clients attached to it. Entries with several matching instances ask
+//! which one first. Also kills one of the heaviest processes in its
+//! sessions (see [`usage`]).
+//!
+//! Detaching sends each client process `SIGTERM`, on which zellij clients
+//! detach and leave the session running. It's not offered for the session
+//! the picker runs in, whose clients can't be told apart from the user's.
+
+use crate::usage::{self, Process};
+use crate::zellij::{self, RunningSession};
+use crate::App;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+#[derive(Clone, Copy)]
+pub enum Action {
+    Kill,
+    Delete,
+    Rename,
+    Detach,
+}
+
+impl Action {
+    fn title(self) -> &'static str {
+        match self {
+            Action::Kill => "Kill",
+            Action::Delete => "Delete",
+            Action::Rename => "Rename",
+            Action::Detach => "Detach",
+        }
+    }
+
+    fn applies_to(self, session: &RunningSession) -> bool {
+        match self {
+            Action::Kill | Action::Rename => !session.exited,
+            Action::Delete => session.exited,
+            Action::Detach => !session.exited && !session.current,
+        }
+    }
+}
+
+pub enum Dialog {
+    /// Several instances qualify; the user picks one
+    Choose {
+        action: Action,
+        instances: Vec<String>,
+        cursor: usize,
+    },
+    ConfirmKill {
+        instance: String,
+    },
+    ConfirmDetach {
+        instance: String,
+        clients: Vec<u32>,
+    },
+    Rename {
+        instance: String,
+        input: String,
+    },
+    /// Picking one of the heaviest processes to kill
+    ChooseProcess {
+        processes: Vec<Process>,
+        cursor: usize,
+    },
+    ConfirmKillProcess {
+        process: Process,
+    },
+}
+
+/// Starts `action` on the highlighted entry.
+pub fn start(app: &mut App, action: Action) {
+    let Some(session) = app.get_selected_session() else { return };
+    let instances: Vec<String> = app
+        .instances_of(session)
+        .filter(|r| action.applies_to(r))
+        .map(|r| r.name.clone())
+        .collect();
+
+    match instances.len() {
+        0 => {
+            let state = match action {
+                Action::Delete => "exited",
+                Action::Detach => "other running",
+                _ => "running",
+            };
+            app.status = Some(format!("{} has no {} session", session.name, state));
+        }
+        1 => proceed(app, action, instances[0].clone()),
+        _ => {
+            app.dialog = Some(Dialog::Choose {
+                action,
+                instances,
+                cursor: 0,
+            })
+        }
+    }
+}
+
+/// Starts killing one of the processes in the highlighted entry's sessions.
+pub fn start_kill_process(app: &mut App) {
+    let Some(index) = app.selected_index() else { return };
+    let processes = app.entry_usage(index).map(|u| u.heaviest).unwrap_or_default();
+    match processes.len() {
+        0 => app.status = Some(format!("{} has no running processes", app.catalog.sessions[index].name)),
+        1 => {
+            app.dialog = Some(Dialog::ConfirmKillProcess {
+                process: processes[0].clone(),
+            })
+        }
+        _ => app.dialog = Some(Dialog::ChooseProcess { processes, cursor: 0 }),
+    }
+}
+
+fn proceed(app: &mut App, action: Action, instance: String) {
+    match action {
+        Action::Kill => app.dialog = Some(Dialog::ConfirmKill { instance }),
+        Action::Rename => {
+            app.dialog = Some(Dialog::Rename {
+                input: instance.clone(),
+                instance,
+            })
+        }
+        Action::Delete => {
+            let result = zellij::delete_session(&instance, true);
+            finish(app, result, format!("Deleted {}", instance));
+        }
+        Action::Detach => match zellij::clients(&instance) {
+            Ok(clients) if clients.is_empty() => app.status = Some(format!("No clients are attached to {}", instance)),
+            Ok(clients) => app.dialog = Some(Dialog::ConfirmDetach { instance, clients }),
+            Err(e) => app.status = Some(format!("{:#}", e)),
+        },
+    }
+}
+
+/// Handles a key while a dialog is open.
+pub fn handle_key(app: &mut App, key: KeyEvent) {
+    let Some(dialog) = app.dialog.take() else { return };
+
+    match dialog {
+        Dialog::Choose { action, instances, cursor } => {
+            let cursor = match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => return,
+                KeyCode::Enter => return proceed(app, action, instances[cursor].clone()),
+                KeyCode::Char('j') | KeyCode::Down => (cursor + 1) % instances.len(),
+                KeyCode::Char('k') | KeyCode::Up => (cursor + instances.len() - 1) % instances.len(),
+                _ => cursor,
+            };
+            app.dialog = Some(Dialog::Choose { action, instances, cursor });
+        }
+        Dialog::ConfirmKill { instance } => match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => {
+                let result = zellij::kill_session(&instance);
+                finish(app, result, format!("Killed {}", instance));
+            }
+            KeyCode::Char('n') | KeyCode::Esc | KeyCode::Char('q') => {}
+            _ => app.dialog = Some(Dialog::ConfirmKill { instance }),
+        },
+        Dialog::ConfirmDetach { instance, clients } => match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => {
+                let result = clients.iter().try_for_each(|&pid| usage::kill(pid));
+                finish(app, result, format!("Detached {} client(s) from {}", clients.len(), instance));
+            }
+            KeyCode::Char('n') | KeyCode::Esc | KeyCode::Char('q') => {}
+            _ => app.dialog = Some(Dialog::ConfirmDetach { instance, clients }),
+        },
+        Dialog::Rename { instance, mut input } => match key.code {
+            KeyCode::Esc => {}
+            KeyCode::Enter => {
+                if input.is_empty() || input == instance {
+                    return;
+                }
+                if app.running.iter().any(|r| r.name == input) {
+                    app.status = Some(format!("A session named {} already exists", input));
+                    app.dialog = Some(Dialog::Rename { instance, input });
+                    return;
+                }
+                let result = zellij::rename_session(&instance, &input);
+                finish(app, result, format!("Renamed {} to {}", instance, input));
+            }
+            KeyCode::Backspace => {
+                input.pop();
+                app.dialog = Some(Dialog::Rename { instance, input });
+            }
+            KeyCode::Char(c) => {
+                // zellij session names can't contain whitespace or slashes
+                if !c.is_whitespace() && c != '/' {
+                    input.push(c);
+                }
+                app.dialog = Some(Dialog::Rename { instance, input });
+            }
+            _ => app.dialog = Some(Dialog::Rename { instance, input }),
+        },
+        Dialog::ChooseProcess { processes, cursor } => {
+            let cursor = match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => return,
+                KeyCode::Enter => {
+                    app.dialog = Some(Dialog::ConfirmKillProcess {
+                        process: processes[cursor].clone(),
+                    });
+                    return;
+                }
+                KeyCode::Char('j') | KeyCode::Down => (cursor + 1) % processes.len(),
+                KeyCode::Char('k') | KeyCode::Up => (cursor + processes.len() - 1) % processes.len(),
+                _ => cursor,
+            };
+            app.dialog = Some(Dialog::ChooseProcess { processes, cursor });
+        }
+        Dialog::ConfirmKillProcess { process } => match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => {
+                let result = usage::kill(process.pid);
+                finish(app, result, format!("Killed {} ({})", process.pid, short_command(&process.command)));
+            }
+            KeyCode::Char('n') | KeyCode::Esc | KeyCode::Char('q') => {}
+            _ => app.dialog = Some(Dialog::ConfirmKillProcess { process }),
+        },
+    }
+}
+
+/// A command line cut down to fit a dialog.
+fn short_command(command: &str) -> String {
+    const MAX: usize = 60;
+    if command.chars().count() > MAX {
+        format!("{}…", command.chars().take(MAX - 1).collect::<String>())
+    } else {
+        command.to_string()
+    }
+}
+
+/// Shows the result in the status line and refreshes the session list.
+fn finish(app: &mut App, result: Result<()>, done: String) {
+    app.status = Some(match result {
+        Ok(()) => done,
+        Err(e) => format!("{:#}", e),
+    });
+    app.running = zellij::list_sessions();
+}
+
+pub fn render(f: &mut Frame, dialog: &Dialog, area: Rect) {
+    let key = Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let (title, lines) = match dialog {
+        Dialog::Choose { action, instances, cursor } => {
+            let mut lines: Vec<Line> = instances
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    if i == *cursor {
+                        Line::from(Span::styled(
+                            format!("▶ {}", name),
+                            Style::default().fg(Color::Black).bg(Color::Blue),
+                        ))
+                    } else {
+                        Line::from(format!("  {}", name))
+                    }
+                })
+                .collect();
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled("⏎", key),
+                Span::styled(" choose  ", dim),
+                Span::styled("esc", key),
+                Span::styled(" cancel", dim),
+            ]));
+            (format!(" {} which session? ", action.title()), lines)
+        }
+        Dialog::ConfirmKill { instance } => (
+            " Kill session ".to_string(),
+            vec![
+                Line::from(vec![
+                    Span::raw("Kill "),
+                    Span::styled(instance.as_str(), Style::default().fg(Color::Yellow)),
+                    Span::raw("?"),
+                ]),
+                Line::from(Span::styled("Its panes and running commands are stopped.", dim)),
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("y", key),
+                    Span::styled(" kill  ", dim),
+                    Span::styled("n", key),
+                    Span::styled(" cancel", dim),
+                ]),
+            ],
+        ),
+        Dialog::ConfirmDetach { instance, clients } => (
+            " Detach clients ".to_string(),
+            vec![
+                Line::from(vec![
+                    Span::raw(format!("Detach {} client(s) from ", clients.len())),
+                    Span::styled(instance.as_str(), Style::default().fg(Color::Yellow)),
+                    Span::raw("?"),
+                ]),
+                Line::from(Span::styled("The session keeps running.", dim)),
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("y", key),
+                    Span::styled(" detach  ", dim),
+                    Span::styled("n", key),
+                    Span::styled(" cancel", dim),
+                ]),
+            ],
+        ),
+        Dialog::ChooseProcess { processes, cursor } => {
+            let mut lines: Vec<Line> = processes
+                .iter()
+                .enumerate()
+                .map(|(i, process)| {
+                    let text = format!(
+                        "{:>6.1}% {:>6}  {}",
+                        process.cpu,
+                        usage::format_bytes(process.rss),
+                        short_command(&process.command)
+                    );
+                    if i == *cursor {
+                        Line::from(Span::styled(
+                            format!("▶ {}", text),
+                            Style::default().fg(Color::Black).bg(Color::Blue),
+                        ))
+                    } else {
+                        Line::from(format!("  {}", text))
+                    }
+                })
+                .collect();
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled("⏎", key),
+                Span::styled(" choose  ", dim),
+                Span::styled("esc", key),
+                Span::styled(" cancel", dim),
+            ]));
+            (" Kill which process? ".to_string(), lines)
+        }
+        Dialog::ConfirmKillProcess { process } => (
+            " Kill process ".to_string(),
+            vec![
+                Line::from(vec![
+                    Span::raw("Kill "),
+                    Span::styled(short_command(&process.command), Style::default().fg(Color::Yellow)),
+                    Span::raw(format!(" (pid {})?", process.pid)),
+                ]),
+                Line::from(Span::styled("It is sent SIGTERM.", dim)),
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("y", key),
+                    Span::styled(" kill  ", dim),
+                    Span::styled("n", key),
+                    Span::styled(" cancel", dim),
+                ]),
+            ],
+        ),
+        Dialog::Rename { instance, input } => (
+            format!(" Rename {} ", instance),
+            vec![
+                Line::from(vec![
+                    Span::styled("> ", Style::default().fg(Color::Blue)),
+                    Span::styled(input.as_str(), Style::default().fg(Color::White)),
+                    Span::styled("_", Style::default().fg(Color::Blue)),
+                ]),
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("⏎", key),
+                    Span::styled(" rename  ", dim),
+                    Span::styled("esc", key),
+                    Span::styled(" cancel", dim),
+                ]),
+            ],
+        ),
+    };
+
+    let width = lines
+        .iter()
+        .map(|l| l.width() as u16)
+        .chain(std::iter::once(title.chars().count() as u16))
+        .max()
+        .unwrap_or(0)
+        .saturating_add(4)
+        .max(30)
+        .min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Blue))
+                .title(title)
+                .title_style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+        ),
+        popup,
+    );
+}
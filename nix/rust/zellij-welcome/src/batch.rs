@@ -0,0 +1,425 @@
+//! Batch actions on the entries marked with Tab/Space: kill their running
+//! sessions, delete their exited ones, start them in the background, warm
+//! their nix devshells or copy their session names. Items run one after
+//! another on a worker thread so the dialog can show progress, including
+//! the latest output of a start's hooks; Esc stops the rest. Devshells warm
+//! in the background instead (see [`crate::warm`]).
+
+use crate::catalog::{Catalog, Session};
+use crate::cli;
+use crate::hooks::{self, Cancel};
+use crate::zellij;
+use crate::App;
+use anyhow::{bail, Context, Result};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+
+#[derive(Clone, Copy)]
+pub enum BatchAction {
+    Kill,
+    DeleteExited,
+    StartBackground,
+}
+
+impl BatchAction {
+    fn verb(self) -> &'static str {
+        match self {
+            BatchAction::Kill => "Killing",
+            BatchAction::DeleteExited => "Deleting",
+            BatchAction::StartBackground => "Starting",
+        }
+    }
+
+    fn done(self) -> &'static str {
+        match self {
+            BatchAction::Kill => "killed",
+            BatchAction::DeleteExited => "deleted",
+            BatchAction::StartBackground => "started",
+        }
+    }
+}
+
+/// Work for one item, owned so it can move to the worker thread
+enum Job {
+    Kill(String),
+    Delete(String),
+    Start(Box<Session>),
+}
+
+impl Job {
+    fn label(&self) -> &str {
+        match self {
+            Job::Kill(name) | Job::Delete(name) => name,
+            Job::Start(session) => &session.name,
+        }
+    }
+
+    /// Runs the job, returning what was done if it's worth showing. Hook
+    /// output goes to `report`.
+    fn run(&self, catalog: &Catalog, report: &(dyn Fn(hooks::Event) + Sync), cancel: &Cancel) -> Result<String> {
+        match self {
+            Job::Kill(name) => zellij::kill_session(name).map(|()| String::new()),
+            Job::Delete(name) => zellij::delete_session(name, false).map(|()| String::new()),
+            Job::Start(session) => cli::start_background(catalog, session, report, cancel).map(|started| started.to_string()),
+        }
+    }
+}
+
+/// What the worker thread reports for item `usize`
+pub enum Progress {
+    /// A line of hook output
+    Output(usize, String),
+    Finished(usize, Result<String, String>),
+}
+
+pub enum ItemState {
+    /// With the latest line of hook output, if any
+    Pending(String),
+    Done(String),
+    Failed(String),
+}
+
+pub enum Batch {
+    /// Choosing what to do with the marked entries
+    Menu,
+    Running {
+        action: BatchAction,
+        items: Vec<(String, ItemState)>,
+        progress: Receiver<Progress>,
+        /// Shared with the worker thread
+        cancel: Cancel,
+    },
+}
+
+impl Batch {
+    fn finished(&self) -> bool {
+        match self {
+            Batch::Menu => false,
+            Batch::Running { items, .. } => items.iter().all(|(_, s)| !matches!(s, ItemState::Pending(_))),
+        }
+    }
+}
+
+fn marked_sessions(app: &App) -> Vec<&Session> {
+    app.marked.iter().map(|&i| &app.catalog.sessions[i]).collect()
+}
+
+/// Handles a key while the batch dialog is open.
+pub fn handle_key(app: &mut App, key: KeyEvent) {
+    let Some(batch) = app.batch.take() else { return };
+
+    if let Batch::Running { cancel, .. } = &batch {
+        // Closing is only possible once every item is through; until then
+        // Esc stops the running item's hooks and skips the rest
+        if batch.finished() && matches!(key.code, KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q')) {
+            summarize(app, &batch);
+            app.marked.clear();
+            app.running = zellij::list_sessions();
+        } else {
+            if key.code == KeyCode::Esc {
+                cancel.cancel();
+            }
+            app.batch = Some(batch);
+        }
+        return;
+    }
+
+    let action = match key.code {
+        KeyCode::Char('k') => BatchAction::Kill,
+        KeyCode::Char('d') => BatchAction::DeleteExited,
+        KeyCode::Char('s') => BatchAction::StartBackground,
+        KeyCode::Char('y') => {
+            copy_names(app);
+            return;
+        }
+        // Builds take minutes, so they go on in the background
+        KeyCode::Char('w') => {
+            let marked: Vec<usize> = app.marked.iter().copied().collect();
+            let queued = app.warm(marked);
+            app.status = Some(match queued {
+                0 => "No devshells to warm among the marked entries".to_string(),
+                n => format!("Warming {} devshell{}", n, if n == 1 { "" } else { "s" }),
+            });
+            app.marked.clear();
+            return;
+        }
+        KeyCode::Esc | KeyCode::Char('q') => return,
+        _ => {
+            app.batch = Some(batch);
+            return;
+        }
+    };
+
+    let jobs = jobs(app, action);
+    if jobs.is_empty() {
+        app.status = Some(match action {
+            BatchAction::Kill => "No running sessions among the marked entries".to_string(),
+            BatchAction::DeleteExited => "No exited sessions among the marked entries".to_string(),
+            BatchAction::StartBackground => "Nothing marked".to_string(),
+        });
+        return;
+    }
+
+    let items = jobs
+        .iter()
+        .map(|job| (job.label().to_string(), ItemState::Pending(String::new())))
+        .collect();
+
+    let (tx, progress) = mpsc::channel();
+    let catalog = app.catalog.clone();
+    let cancel = Cancel::new();
+    let cancelled = cancel.clone();
+    std::thread::spawn(move || {
+        for (i, job) in jobs.iter().enumerate() {
+            let result = if cancelled.is_cancelled() {
+                Err("cancelled".to_string())
+            } else {
+                let report = |event| {
+                    if let hooks::Event::Stdout(line) | hooks::Event::Stderr(line) = event {
+                        let _ = tx.send(Progress::Output(i, line));
+                    }
+                };
+                job.run(&catalog, &report, &cancelled).map_err(|e| format!("{:#}", e))
+            };
+            if tx.send(Progress::Finished(i, result)).is_err() {
+                break;
+            }
+        }
+    });
+
+    app.batch = Some(Batch::Running {
+        action,
+        items,
+        progress,
+        cancel,
+    });
+}
+
+fn jobs(app: &App, action: BatchAction) -> Vec<Job> {
+    let mut jobs = Vec::new();
+    for session in marked_sessions(app) {
+        match action {
+            BatchAction::Kill => jobs.extend(
+                app.instances_of(session)
+                    .filter(|r| !r.exited)
+                    .map(|r| Job::Kill(r.name.clone())),
+            ),
+            BatchAction::DeleteExited => jobs.extend(
+                app.instances_of(session)
+                    .filter(|r| r.exited)
+                    .map(|r| Job::Delete(r.name.clone())),
+            ),
+            BatchAction::StartBackground => jobs.push(Job::Start(Box::new(session.clone()))),
+        }
+    }
+    jobs
+}
+
+/// Applies progress reported by the worker thread.
+pub fn poll(app: &mut App) {
+    if let Some(Batch::Running { items, progress, .. }) = &mut app.batch {
+        while let Ok(progress) = progress.try_recv() {
+            match progress {
+                Progress::Output(i, line) if !line.trim().is_empty() => items[i].1 = ItemState::Pending(line),
+                Progress::Output(..) => {}
+                Progress::Finished(i, Ok(detail)) => items[i].1 = ItemState::Done(detail),
+                Progress::Finished(i, Err(e)) => items[i].1 = ItemState::Failed(e),
+            }
+        }
+    }
+}
+
+fn summarize(app: &mut App, batch: &Batch) {
+    if let Batch::Running { action, items, .. } = batch {
+        let failed = items.iter().filter(|(_, s)| matches!(s, ItemState::Failed(_))).count();
+        let mut summary = format!("{} {}", items.len() - failed, action.done());
+        if failed > 0 {
+            summary.push_str(&format!(", {} failed", failed));
+        }
+        app.status = Some(summary);
+    }
+}
+
+/// Copies the marked entries' session names (or the entry names, for entries
+/// without sessions), one per line.
+fn copy_names(app: &mut App) {
+    let mut names = Vec::new();
+    for session in marked_sessions(app) {
+        let instances: Vec<String> = app.instances_of(session).map(|r| r.name.clone()).collect();
+        if instances.is_empty() {
+            names.push(session.name.clone());
+        } else {
+            names.extend(instances);
+        }
+    }
+
+    app.status = Some(match copy_to_clipboard(&names.join("\n")) {
+        Ok(()) => format!("Copied {} name{}", names.len(), if names.len() == 1 { "" } else { "s" }),
+        Err(e) => format!("{:#}", e),
+    });
+}
+
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    let tools: [&[&str]; 4] = [
+        &["pbcopy"],
+        &["wl-copy"],
+        &["xclip", "-selection", "clipboard"],
+        &["xsel", "--clipboard", "--input"],
+    ];
+    for tool in tools {
+        let Ok(mut child) = Command::new(tool[0])
+            .args(&tool[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+
+    // No clipboard tool: ask the terminal via OSC 52, which zellij forwards
+    let mut tty = std::fs::OpenOptions::new()
+        .write(true)
+        .open("/dev/tty")
+        .context("No clipboard tool found")?;
+    let encoded = base64(text.as_bytes());
+    if write!(tty, "\x1b]52;c;{}\x07", encoded).is_err() {
+        bail!("No clipboard tool found");
+    }
+    Ok(())
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+pub fn render(f: &mut Frame, app: &App, batch: &Batch, area: Rect) {
+    let key = Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let (title, lines) = match batch {
+        Batch::Menu => {
+            let marked = marked_sessions(app);
+            let running: usize = marked
+                .iter()
+                .map(|s| app.instances_of(s).filter(|r| !r.exited).count())
+                .sum();
+            let exited: usize = marked
+                .iter()
+                .map(|s| app.instances_of(s).filter(|r| r.exited).count())
+                .sum();
+            let flakes = app
+                .marked
+                .iter()
+                .filter(|&&i| app.working_dir(i).is_some_and(|dir| dir.join("flake.nix").exists()))
+                .count();
+            let option = |k: &'static str, text: String| {
+                Line::from(vec![Span::styled(k, key), Span::raw(format!("  {}", text))])
+            };
+            (
+                format!(" {} marked ", marked.len()),
+                vec![
+                    option("k", format!("Kill running sessions ({})", running)),
+                    option("d", format!("Delete exited sessions ({})", exited)),
+                    option("s", format!("Start in the background ({})", marked.len())),
+                    option("w", format!("Warm nix devshells ({})", flakes)),
+                    option("y", "Copy session names".to_string()),
+                    Line::from(""),
+                    Line::from(vec![Span::styled("esc", key), Span::styled(" cancel", dim)]),
+                ],
+            )
+        }
+        Batch::Running { action, items, .. } => {
+            let completed = items.iter().filter(|(_, s)| !matches!(s, ItemState::Pending(_))).count();
+            let mut lines: Vec<Line> = items
+                .iter()
+                .map(|(name, state)| match state {
+                    ItemState::Pending(output) => Line::from(vec![
+                        Span::styled("  … ", dim),
+                        Span::raw(name.clone()),
+                        Span::styled(format!("  {}", output), dim),
+                    ]),
+                    ItemState::Done(detail) => Line::from(vec![
+                        Span::styled("  ✓ ", Style::default().fg(Color::Green)),
+                        Span::raw(name.clone()),
+                        Span::styled(format!("  {}", detail), dim),
+                    ]),
+                    ItemState::Failed(e) => Line::from(vec![
+                        Span::styled("  ✗ ", Style::default().fg(Color::Red)),
+                        Span::raw(name.clone()),
+                        Span::styled(format!("  {}", e), dim),
+                    ]),
+                })
+                .collect();
+            lines.push(Line::from(""));
+            if batch.finished() {
+                lines.push(Line::from(vec![Span::styled("⏎", key), Span::styled(" close", dim)]));
+            } else {
+                lines.push(Line::from(vec![
+                    Span::styled("working…  ", dim),
+                    Span::styled("esc", key),
+                    Span::styled(" stop", dim),
+                ]));
+            }
+            (format!(" {} {}/{} ", action.verb(), completed, items.len()), lines)
+        }
+    };
+
+    let width = lines
+        .iter()
+        .map(|l| l.width() as u16)
+        .chain(std::iter::once(title.chars().count() as u16))
+        .max()
+        .unwrap_or(0)
+        .saturating_add(4)
+        .max(34)
+        .min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Blue))
+                .title(title)
+                .title_style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+        ),
+        popup,
+    );
+}
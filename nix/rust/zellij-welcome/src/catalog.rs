@@ -0,0 +1,433 @@
+//! The list of known sessions.
+//!
+//! Defaults to the built-in list below; can be overridden with
+//! `~/.config/zellij-welcome/catalog.toml`:
+//!
+//! ```toml
+//! layouts_dir = "~/.config/zellij/layouts"
+//!
+//! [tracking]
+//! idle_minutes = 15            # gaps without activity longer than this don't count
+//!
+//! [create]                     # the "create session" row for search queries
+//! layout = "default"           # the default
+//! always = false               # also offer it when entries match
+//! project_dirs = ["~/dev/work", "~/dev/private"] # a dir named like the query is the cwd
+//!
+//! [discover]                   # git repositories offered as project entries
+//! roots = ["~/dev/work", "~/dev/private"]
+//! depth = 3                    # directory levels searched below each root
+//! layout = "default"           # layout of project sessions
+//!
+//! [nix]
+//! auto_warm = true             # rebuild devshells when flake.lock changes (see `warm`)
+//!
+//! [[session]]
+//! name = "colony"
+//! description = "Multi-agent dev environments"
+//! layout = "colony"            # defaults to the name
+//! cwd = "~/dev/private/colony" # defaults to the layout's cwd
+//! notes = "Run `make init` once after a fresh clone"
+//! alias = "zco"                # shell shortcut (see `shell-init`)
+//! key = "c"                    # opens it from the picker's normal mode
+//! template = "project"         # generate the layout (see `generate-layouts`)
+//! autostart = true             # started in the background by `warmup`
+//! naming = "{name}-{n}"        # instance names (see `naming`)
+//! worktrees = true             # an entry per git worktree of the cwd
+//! env_source = "flake"         # exported into new sessions (see `env`)
+//! pre_launch = ["docker compose up -d"] # run in the cwd before creating a session
+//! post_launch = ["git fetch"]  # and after
+//! services = [5432, "http://localhost:3000/health"] # health badges (see `health`)
+//!
+//! [session.vars]               # template variables, over the top-level [vars]
+//! flake = "."
+//!
+//! [session.env]                # exported into new sessions, over env_source
+//! RUST_LOG = "debug"
+//! ```
+
+use crate::health;
+use crate::naming;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize, Clone)]
+pub struct Catalog {
+    #[serde(default = "default_layouts_dir")]
+    pub layouts_dir: String,
+    #[serde(default, rename = "session")]
+    pub sessions: Vec<Session>,
+    /// Template variables shared by all sessions
+    #[serde(default)]
+    pub vars: toml::Table,
+    #[serde(default)]
+    pub tracking: Tracking,
+    #[serde(default)]
+    pub create: Create,
+    #[serde(default)]
+    pub discover: Discover,
+    #[serde(default)]
+    pub nix: Nix,
+}
+
+/// Where to look for git repositories to offer as project entries
+#[derive(Deserialize, Clone)]
+pub struct Discover {
+    #[serde(default)]
+    pub roots: Vec<String>,
+    /// How many directory levels below a root are searched
+    #[serde(default = "default_discover_depth")]
+    pub depth: usize,
+    /// Layout project sessions are created with
+    #[serde(default = "default_create_layout")]
+    pub layout: String,
+}
+
+impl Default for Discover {
+    fn default() -> Self {
+        Self {
+            roots: Vec::new(),
+            depth: default_discover_depth(),
+            layout: default_create_layout(),
+        }
+    }
+}
+
+/// Flake devshells of the entries
+#[derive(Deserialize, Clone, Default)]
+pub struct Nix {
+    /// Rebuild devshells built for an older `flake.lock` in the background
+    #[serde(default)]
+    pub auto_warm: bool,
+}
+
+/// The "create session" row offered for a search query
+#[derive(Deserialize, Clone)]
+pub struct Create {
+    /// Layout of sessions created from the query
+    #[serde(default = "default_create_layout")]
+    pub layout: String,
+    /// Offer the row below every search, not only when nothing matches
+    #[serde(default)]
+    pub always: bool,
+    /// A directory in one of these named like the query becomes the cwd
+    #[serde(default)]
+    pub project_dirs: Vec<String>,
+}
+
+impl Default for Create {
+    fn default() -> Self {
+        Self {
+            layout: default_create_layout(),
+            always: false,
+            project_dirs: Vec::new(),
+        }
+    }
+}
+
+impl Create {
+    /// The directory in `project_dirs` named `name`, ignoring case.
+    pub fn resolve_cwd(&self, name: &str) -> Option<PathBuf> {
+        self.project_dirs.iter().find_map(|dir| {
+            std::fs::read_dir(expand_home(dir))
+                .ok()?
+                .flatten()
+                .find(|e| e.file_name().to_string_lossy().eq_ignore_ascii_case(name) && e.path().is_dir())
+                .map(|e| e.path())
+        })
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Tracking {
+    #[serde(default = "default_idle_minutes")]
+    pub idle_minutes: u64,
+}
+
+impl Default for Tracking {
+    fn default() -> Self {
+        Self {
+            idle_minutes: default_idle_minutes(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Session {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub layout: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Shell alias that opens this session
+    #[serde(default)]
+    pub alias: Option<String>,
+    /// Hotkey that opens this session from the picker's normal mode
+    #[serde(default)]
+    pub key: Option<char>,
+    /// Layout template this session's layout is generated from
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub vars: toml::Table,
+    /// Started in the background by `warmup`
+    #[serde(default)]
+    pub autostart: bool,
+    /// Template for instance names (see [`naming`])
+    #[serde(default)]
+    pub naming: Option<String>,
+    /// Variables exported into new sessions, over the `env_source` ones
+    #[serde(default)]
+    pub env: toml::Table,
+    /// Where else new sessions get variables from (see [`env`](crate::env))
+    #[serde(default)]
+    pub env_source: Option<String>,
+    /// Commands run before a new session is created (see [`hooks`](crate::hooks))
+    #[serde(default)]
+    pub pre_launch: Vec<String>,
+    /// Commands run once a new session is created
+    #[serde(default)]
+    pub post_launch: Vec<String>,
+    /// Local ports and health URLs whose state the picker shows
+    #[serde(default)]
+    pub services: Vec<health::Service>,
+    /// List each further git worktree of `cwd` as an entry of its own
+    #[serde(default)]
+    pub worktrees: bool,
+    /// Found by discovery rather than listed in the catalog
+    #[serde(skip)]
+    pub project: bool,
+    /// For a worktree entry, the entry it was expanded from
+    #[serde(skip)]
+    pub worktree_of: Option<String>,
+    /// Instance names of the worktree entries expanded from this one, which
+    /// it doesn't own despite their `name-` prefix
+    #[serde(skip)]
+    pub worktree_instances: Vec<String>,
+}
+
+impl Session {
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            layout: None,
+            cwd: None,
+            notes: None,
+            alias: None,
+            key: None,
+            template: None,
+            vars: toml::Table::new(),
+            autostart: false,
+            naming: None,
+            env: toml::Table::new(),
+            env_source: None,
+            pre_launch: Vec::new(),
+            post_launch: Vec::new(),
+            services: Vec::new(),
+            worktrees: false,
+            project: false,
+            worktree_of: None,
+            worktree_instances: Vec::new(),
+        }
+    }
+
+    /// Name of the zellij layout this session is created from.
+    pub fn layout_name(&self) -> &str {
+        self.layout.as_deref().unwrap_or(&self.name)
+    }
+
+    fn with_alias(mut self, alias: &str) -> Self {
+        self.alias = Some(alias.to_string());
+        self
+    }
+
+    /// A `[[session]]` table with the fields a new entry needs.
+    fn to_toml(&self) -> String {
+        let mut out = format!("\n[[session]]\nname = {}\n", toml::Value::from(self.name.as_str()));
+        let fields = [
+            ("description", Some(self.description.as_str()).filter(|d| !d.is_empty())),
+            ("layout", self.layout.as_deref()),
+            ("cwd", self.cwd.as_deref()),
+            ("alias", self.alias.as_deref()),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                out.push_str(&format!("{} = {}\n", key, toml::Value::from(value)));
+            }
+        }
+        out
+    }
+
+    /// Whether a zellij session name is an instance of this entry.
+    pub fn owns(&self, instance: &str) -> bool {
+        !self.worktree_instances.iter().any(|w| w == instance) && naming::matches(self, instance)
+    }
+
+    /// The directory to create sessions in, for entries generated from a
+    /// directory (projects and worktrees), whose layout can't know it.
+    pub fn launch_cwd(&self) -> Option<PathBuf> {
+        let generated = self.project || self.worktree_of.is_some();
+        self.cwd.as_deref().filter(|_| generated).map(expand_home)
+    }
+}
+
+impl Catalog {
+    pub fn load() -> Result<Self> {
+        let path = config_dir().join("catalog.toml");
+        if !path.exists() {
+            return Ok(Self::builtin());
+        }
+
+        let source = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let catalog: Catalog = toml::from_str(&source)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        Ok(catalog)
+    }
+
+    fn builtin() -> Self {
+        Self {
+            layouts_dir: default_layouts_dir(),
+            sessions: vec![
+                Session::new("new", "Start a new session"),
+                Session::new("energyboard", "Energy management portal").with_alias("zeb"),
+                Session::new("easyasset", "Asset tracking platform").with_alias("zea"),
+                Session::new("colony", "Multi-agent dev environments").with_alias("zco"),
+                Session::new("backoffice", "Admin backend systems").with_alias("zbo"),
+                Session::new("gappel-solutions", "Company solutions").with_alias("zgs"),
+                Session::new("decon", "Decon project").with_alias("zdc"),
+                Session::new("screensaver", "Screensaver development").with_alias("zsc"),
+                Session::new("lazychat", "Lazychat TUI for Claude sessions").with_alias("zlc"),
+                Session::new("elixir", "Elixir projects").with_alias("zex"),
+                Session::new("msp", "Smartpower MSP platform").with_alias("zms"),
+                Session::new("smartflex", "SMARTFLEX foundation system").with_alias("zsf"),
+                Session::new("lazylink", "TUI task board + agent coordination").with_alias("zll"),
+                Session::new("welcome", "Return to this screen"),
+            ],
+            vars: toml::Table::new(),
+            tracking: Tracking::default(),
+            create: Create::default(),
+            discover: Discover::default(),
+            nix: Nix::default(),
+        }
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Session> {
+        self.sessions.iter().find(|s| s.name == name)
+    }
+
+    pub fn layouts_dir(&self) -> PathBuf {
+        expand_home(&self.layouts_dir)
+    }
+
+    pub fn layout_path(&self, session: &Session) -> PathBuf {
+        self.layouts_dir().join(format!("{}.kdl", session.layout_name()))
+    }
+
+    /// The directory an entry works in: its `cwd`, else its layout's.
+    pub fn project_root(&self, session: &Session) -> Option<PathBuf> {
+        let cwd = match &session.cwd {
+            Some(cwd) => cwd.clone(),
+            None => crate::layout::load(&self.layout_path(session)).ok()?.cwd?,
+        };
+        Some(expand_home(cwd.trim_end_matches('/')))
+    }
+}
+
+/// Of `(index, root)` pairs, the index whose root contains `dir` most
+/// closely; the first one listed wins ties.
+pub fn deepest_root(dir: &Path, roots: impl IntoIterator<Item = (usize, PathBuf)>) -> Option<usize> {
+    // Symlinked roots still match the directories they point to
+    let real = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let dir = real(dir);
+    roots
+        .into_iter()
+        .filter_map(|(index, root)| {
+            let root = real(&root);
+            dir.starts_with(&root).then(|| (root.components().count(), index))
+        })
+        .max_by(|(a, ia), (b, ib)| a.cmp(b).then(ib.cmp(ia)))
+        .map(|(_, index)| index)
+}
+
+/// Appends `session` to `catalog.toml`. Without one yet, the file is
+/// started from `catalog`, so the entries in use don't disappear; discovered
+/// projects and worktrees are left out, as they're found again on each run.
+pub fn append_session(catalog: &Catalog, session: &Session) -> Result<()> {
+    let path = config_dir().join("catalog.toml");
+    let mut source = if path.exists() {
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?
+    } else {
+        let mut source = format!("layouts_dir = {}\n", toml::Value::from(catalog.layouts_dir.as_str()));
+        for existing in catalog.sessions.iter().filter(|s| !s.project && s.worktree_of.is_none()) {
+            source.push_str(&existing.to_toml());
+        }
+        source
+    };
+    source.push_str(&session.to_toml());
+
+    std::fs::create_dir_all(config_dir())
+        .and_then(|()| std::fs::write(&path, source))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn default_layouts_dir() -> String {
+    "~/.config/zellij/layouts".to_string()
+}
+
+fn default_create_layout() -> String {
+    "default".to_string()
+}
+
+fn default_discover_depth() -> usize {
+    3
+}
+
+fn default_idle_minutes() -> u64 {
+    15
+}
+
+pub fn config_dir() -> PathBuf {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("zellij-welcome"),
+        _ => home_dir().join(".config").join("zellij-welcome"),
+    }
+}
+
+/// Where state like the launch history is kept.
+pub fn data_dir() -> PathBuf {
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("zellij-welcome"),
+        _ => home_dir().join(".local/share/zellij-welcome"),
+    }
+}
+
+pub fn home_dir() -> PathBuf {
+    std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default()
+}
+
+/// Expands a leading `~` to `$HOME`.
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            home_dir().join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Inverse of [`expand_home`], for display.
+pub fn contract_home(path: &Path) -> String {
+    let home = home_dir();
+    match path.strip_prefix(&home) {
+        Ok(rest) if !home.as_os_str().is_empty() => format!("~/{}", rest.display()),
+        _ => path.display().to_string(),
+    }
+}
@@ -0,0 +1,208 @@
+//! Non-interactive subcommands, for scripts and shell bindings.
+
+use crate::catalog::{self, Catalog, Session};
+use crate::discover;
+use crate::env;
+use crate::hooks::{self, Cancel, Stage};
+use crate::naming;
+use crate::warm;
+use crate::zellij::{self, RunningSession};
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    name: &'a str,
+    description: &'a str,
+    layout: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cwd: Option<&'a str>,
+    instances: Vec<Instance<'a>>,
+}
+
+#[derive(Serialize)]
+struct Instance<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    age_secs: Option<u64>,
+    exited: bool,
+    current: bool,
+}
+
+pub fn find<'a>(catalog: &'a Catalog, name: &str) -> Result<&'a Session> {
+    catalog
+        .find(name)
+        .ok_or_else(|| anyhow!("`{}` is not a catalog entry (see `zellij-welcome list`)", name))
+}
+
+/// The entry whose project root `dir` is deepest inside of, including the
+/// projects found by the last discovery scan.
+pub fn find_by_dir(catalog: &mut Catalog, dir: &Path) -> Result<Session> {
+    let projects = discover::entries(catalog, &discover::load_cache());
+    catalog.sessions.extend(projects);
+    let roots = catalog
+        .sessions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| Some((i, catalog.project_root(s)?)));
+    let index = catalog::deepest_root(dir, roots)
+        .ok_or_else(|| anyhow!("No catalog entry or project contains {}", dir.display()))?;
+    Ok(catalog.sessions[index].clone())
+}
+
+/// Prints the catalog with each entry's instances.
+pub fn list(catalog: &Catalog, json: bool) -> Result<()> {
+    let running = zellij::list_sessions();
+    let entries: Vec<Entry> = catalog
+        .sessions
+        .iter()
+        .map(|session| Entry {
+            name: &session.name,
+            description: &session.description,
+            layout: session.layout_name(),
+            cwd: session.cwd.as_deref(),
+            instances: running
+                .iter()
+                .filter(|r| session.owns(&r.name))
+                .map(|r| Instance {
+                    name: &r.name,
+                    age_secs: r.age.map(|a| a.as_secs()),
+                    exited: r.exited,
+                    current: r.current,
+                })
+                .collect(),
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let width = entries.iter().map(|e| e.name.len()).max().unwrap_or(0);
+    for entry in &entries {
+        println!("{:<width$}  {}", entry.name, entry.description, width = width);
+        for instance in &entry.instances {
+            let age = instance
+                .age_secs
+                .map(|secs| zellij::format_age(Duration::from_secs(secs)))
+                .unwrap_or_default();
+            let state = if instance.exited {
+                " exited"
+            } else if instance.current {
+                " current"
+            } else {
+                ""
+            };
+            println!("{:<width$}  {} {}{}", "", instance.name, age, state, width = width);
+        }
+    }
+    Ok(())
+}
+
+/// Kills the session called `name`, or else every instance of the catalog
+/// entry called `name`.
+pub fn kill(catalog: &Catalog, name: &str) -> Result<()> {
+    let running = zellij::list_sessions();
+    let targets: Vec<&RunningSession> = match running.iter().find(|r| r.name == name) {
+        Some(exact) => vec![exact],
+        None => match catalog.find(name) {
+            Some(session) => running
+                .iter()
+                .filter(|r| !r.exited && session.owns(&r.name))
+                .collect(),
+            None => Vec::new(),
+        },
+    };
+
+    if targets.is_empty() {
+        bail!("No running session or catalog entry instance matches `{}`", name);
+    }
+    for target in targets {
+        if target.exited {
+            zellij::delete_session(&target.name, false)?;
+            println!("deleted {}", target.name);
+        } else {
+            zellij::kill_session(&target.name)?;
+            println!("killed {}", target.name);
+        }
+    }
+    Ok(())
+}
+
+/// What [`start_background`] did
+pub enum Started {
+    AlreadyRunning(String),
+    Resurrected(String),
+    Created(String),
+}
+
+impl std::fmt::Display for Started {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Started::AlreadyRunning(name) => write!(f, "{} is already running", name),
+            Started::Resurrected(name) => write!(f, "resurrected {}", name),
+            Started::Created(name) => write!(f, "created {}", name),
+        }
+    }
+}
+
+/// Makes sure an instance of `session` runs without attaching to it:
+/// resurrects an exited instance, or else creates a new one, reporting hook
+/// output to `report`; `cancel` aborts it while hooks run.
+pub fn start_background(
+    catalog: &Catalog,
+    session: &Session,
+    report: &(dyn Fn(hooks::Event) + Sync),
+    cancel: &Cancel,
+) -> Result<Started> {
+    let running = zellij::list_sessions();
+    let mut instances = running.iter().filter(|r| session.owns(&r.name));
+    if let Some(live) = instances.clone().find(|r| !r.exited) {
+        return Ok(Started::AlreadyRunning(live.name.clone()));
+    }
+    if let Some(exited) = instances.next() {
+        zellij::resurrect_background(&exited.name)?;
+        return Ok(Started::Resurrected(exited.name.clone()));
+    }
+
+    let name = naming::instance_name(catalog, session, &running)?;
+    let env = env::resolve(catalog, session)?;
+    hooks::run(catalog, session, &name, Stage::Pre, &env, report, cancel)?;
+    zellij::create_background(&name, session.layout_name(), session.launch_cwd().as_deref(), &env)?;
+    hooks::run(catalog, session, &name, Stage::Post, &env, report, cancel)?;
+    Ok(Started::Created(name))
+}
+
+/// Starts every `autostart` entry in the background, after rebuilding
+/// outdated devshells with `nix.auto_warm`. Fails if any of them failed,
+/// after trying them all.
+pub fn warmup(catalog: &Catalog) -> Result<()> {
+    // First, so the new sessions' panes find their devshells built
+    let warmed = if catalog.nix.auto_warm {
+        let outdated: Vec<_> = warm::targets(catalog, &catalog.sessions)
+            .into_iter()
+            .filter(warm::Target::is_outdated)
+            .collect();
+        warm::build_all(&outdated)
+    } else {
+        Ok(())
+    };
+
+    let mut failed = 0;
+    for session in catalog.sessions.iter().filter(|s| s.autostart) {
+        match start_background(catalog, session, &hooks::print, &Cancel::none()) {
+            Ok(started) => println!("{}", started),
+            Err(e) => {
+                eprintln!("{}: {:#}", session.name, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{} autostart session(s) failed to start", failed);
+    }
+    warmed
+}
@@ -0,0 +1,274 @@
+//! Discovery of git repositories under `discover.roots`, offered in the
+//! picker as project entries below the catalog's own. The last scan is
+//! cached in `~/.local/share/zellij-welcome/projects.json` so the picker
+//! can list them right away while a fresh scan runs in the background.
+//!
+//! The walk stops at `discover.depth` levels below a root, doesn't descend
+//! into repositories or hidden directories, and skips directories matched by
+//! a `.gitignore` or `.ignore` along the way.
+
+use crate::catalog::{self, Catalog, Discover, Session};
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// Repositories found by the last scan.
+pub fn load_cache() -> Vec<PathBuf> {
+    std::fs::read_to_string(path())
+        .ok()
+        .and_then(|source| serde_json::from_str(&source).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_cache(repos: &[PathBuf]) -> Result<()> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(repos)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn path() -> PathBuf {
+    catalog::data_dir().join("projects.json")
+}
+
+/// Walks the roots for git repositories, sorted by path.
+pub fn scan(config: &Discover) -> Vec<PathBuf> {
+    let mut repos = Vec::new();
+    for root in &config.roots {
+        walk(&catalog::expand_home(root), config.depth, &mut Vec::new(), &mut repos);
+    }
+    repos.sort();
+    repos.dedup();
+    repos
+}
+
+/// Ignore patterns of one directory on the way down, relative to it
+struct Ignores {
+    base: PathBuf,
+    patterns: Vec<String>,
+}
+
+fn walk(dir: &Path, depth: usize, ignores: &mut Vec<Ignores>, repos: &mut Vec<PathBuf>) {
+    if dir.join(".git").exists() {
+        repos.push(dir.to_path_buf());
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+
+    ignores.push(Ignores {
+        base: dir.to_path_buf(),
+        patterns: read_ignores(dir),
+    });
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        // Symlinks are skipped, so loops can't happen
+        let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+        if is_dir && !hidden && !is_ignored(&path, ignores) {
+            walk(&path, depth - 1, ignores, repos);
+        }
+    }
+    ignores.pop();
+}
+
+fn read_ignores(dir: &Path) -> Vec<String> {
+    IGNORE_FILES
+        .iter()
+        .filter_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+        .flat_map(|source| {
+            source
+                .lines()
+                .map(str::trim)
+                // Negations can only re-include, which never hides a directory
+                .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+                .map(|line| line.trim_end_matches('/').to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Whether a directory matches an ignore pattern of any directory above it.
+fn is_ignored(path: &Path, ignores: &[Ignores]) -> bool {
+    ignores.iter().any(|ignore| {
+        let Ok(relative) = path.strip_prefix(&ignore.base) else { return false };
+        let relative = relative.to_string_lossy();
+        let name = relative.rsplit('/').next().unwrap_or(&relative);
+        ignore.patterns.iter().any(|pattern| match pattern.strip_prefix('/') {
+            // Anchored patterns match from the ignore file's directory
+            Some(anchored) => glob(anchored, &relative),
+            None if pattern.contains('/') => glob(pattern, &relative),
+            None => glob(pattern, name),
+        })
+    })
+}
+
+/// Matches `*` (any run of characters but `/`) and `?` (one character).
+fn glob(pattern: &str, s: &str) -> bool {
+    match pattern.chars().next() {
+        None => s.is_empty(),
+        Some('*') => {
+            let rest = &pattern[1..];
+            let max = s.find('/').unwrap_or(s.len());
+            (0..=max).filter(|&i| s.is_char_boundary(i)).any(|i| glob(rest, &s[i..]))
+        }
+        Some('?') => s
+            .chars()
+            .next()
+            .is_some_and(|c| c != '/' && glob(&pattern[1..], &s[c.len_utf8()..])),
+        Some(c) => s.starts_with(c) && glob(&pattern[c.len_utf8()..], &s[c.len_utf8()..]),
+    }
+}
+
+/// Project entries for `repos`, leaving out the ones the catalog already
+/// covers (same project root or name). Names clashing among projects get their parent
+/// directory's name prepended.
+pub fn entries(catalog: &Catalog, repos: &[PathBuf]) -> Vec<Session> {
+    let covered: HashSet<PathBuf> = catalog
+        .sessions
+        .iter()
+        .filter_map(|s| catalog.project_root(s))
+        .collect();
+    let repos: Vec<&PathBuf> = repos.iter().filter(|repo| !covered.contains(*repo)).collect();
+
+    let file_name = |path: &Path| path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut entries: Vec<Session> = Vec::new();
+    for repo in &repos {
+        let mut name = file_name(repo);
+        let clashes = repos.iter().filter(|other| file_name(other) == name).count() > 1;
+        if clashes {
+            let parent = repo.parent().map(file_name).unwrap_or_default();
+            name = format!("{}-{}", parent, name);
+        }
+        let name: String = name.chars().map(|c| if c.is_whitespace() { '-' } else { c }).collect();
+        if catalog.find(&name).is_some() || entries.iter().any(|e| e.name == name) {
+            continue;
+        }
+
+        let description = repo
+            .parent()
+            .map(|parent| format!("project in {}", catalog::contract_home(parent)))
+            .unwrap_or_default();
+        let mut session = Session::new(name, description);
+        session.layout = Some(catalog.discover.layout.clone());
+        session.cwd = Some(catalog::contract_home(repo));
+        session.project = true;
+        entries.push(session);
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(source: &str) -> Catalog {
+        toml::from_str(source).unwrap()
+    }
+
+    fn names(entries: &[Session]) -> Vec<&str> {
+        entries.iter().map(|s| s.name.as_str()).collect()
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob("target", "target"));
+        assert!(!glob("target", "targets"));
+        assert!(glob("*.tmp", "a.tmp"));
+        assert!(glob("*", ""));
+        assert!(!glob("*", "a/b"));
+        assert!(glob("build-?", "build-1"));
+        assert!(!glob("build-?", "build-"));
+        assert!(!glob("a?b", "a/b"));
+        assert!(glob("vendor/*", "vendor/x"));
+        assert!(glob("ä*", "äöü"));
+    }
+
+    #[test]
+    fn ignore_patterns() {
+        let ignores = [Ignores {
+            base: PathBuf::from("/r"),
+            patterns: vec!["node_modules".into(), "/build".into(), "docs/*".into()],
+        }];
+        // Unanchored names match at any depth
+        assert!(is_ignored(Path::new("/r/node_modules"), &ignores));
+        assert!(is_ignored(Path::new("/r/a/node_modules"), &ignores));
+        // Anchored ones only below the ignore file
+        assert!(is_ignored(Path::new("/r/build"), &ignores));
+        assert!(!is_ignored(Path::new("/r/a/build"), &ignores));
+        // Patterns with a slash match the relative path
+        assert!(is_ignored(Path::new("/r/docs/old"), &ignores));
+        assert!(!is_ignored(Path::new("/r/a/docs/old"), &ignores));
+        assert!(!is_ignored(Path::new("/elsewhere/node_modules"), &ignores));
+    }
+
+    #[test]
+    fn scans_for_repositories() {
+        let root = std::env::temp_dir().join(format!("zellij-welcome-discover-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["a/.git", "group/b/.git", "a/nested/.git", "skipped/c/.git", ".hidden/d/.git", "x/y/z/e/.git"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join(".gitignore"), "# comment\nskipped/\n!a\n").unwrap();
+
+        let config = Discover {
+            roots: vec![root.to_string_lossy().into_owned()],
+            depth: 3,
+            layout: "default".into(),
+        };
+        let found = scan(&config);
+        let _ = std::fs::remove_dir_all(&root);
+        // Not inside repositories, ignored or hidden directories, or too deep
+        assert_eq!(found, [root.join("a"), root.join("group/b")]);
+    }
+
+    #[test]
+    fn entries_skip_covered_repositories_and_names() {
+        let catalog = catalog(
+            r#"
+            [discover]
+            layout = "dev"
+
+            [[session]]
+            name = "colony"
+            cwd = "/src/colony/"
+
+            [[session]]
+            name = "api"
+            "#,
+        );
+        let repos: Vec<PathBuf> = ["/src/colony", "/src/api", "/src/web", "/src/my app"].iter().map(PathBuf::from).collect();
+        let entries = entries(&catalog, &repos);
+        assert_eq!(names(&entries), ["web", "my-app"]);
+        assert!(entries.iter().all(|e| e.project && e.layout.as_deref() == Some("dev")));
+        assert_eq!(entries[0].cwd.as_deref(), Some("/src/web"));
+        assert_eq!(entries[0].description, "project in /src");
+    }
+
+    #[test]
+    fn entries_skip_repositories_of_layouts() {
+        let layouts = std::env::temp_dir().join(format!("zellij-welcome-layouts-{}", std::process::id()));
+        std::fs::create_dir_all(&layouts).unwrap();
+        std::fs::write(layouts.join("colony.kdl"), "layout cwd=\"/src/colony/\" {\n    pane\n}\n").unwrap();
+        let catalog = catalog(&format!(
+            "layouts_dir = {:?}\n\n[[session]]\nname = \"hive\"\nlayout = \"colony\"\n",
+            layouts.to_string_lossy()
+        ));
+        let repos: Vec<PathBuf> = ["/src/colony", "/src/web"].iter().map(PathBuf::from).collect();
+        let entries = entries(&catalog, &repos);
+        let _ = std::fs::remove_dir_all(&layouts);
+        assert_eq!(names(&entries), ["web"]);
+    }
+
+    #[test]
+    fn clashing_names_get_their_parent() {
+        let repos: Vec<PathBuf> = ["/work/a", "/private/a", "/work/b"].iter().map(PathBuf::from).collect();
+        assert_eq!(names(&entries(&catalog(""), &repos)), ["work-a", "private-a", "b"]);
+    }
+}
@@ -0,0 +1,357 @@
+//! Environments for new sessions: an entry's `env` table on top of an
+//! optional `env_source`:
+//!
+//! | `env_source`         | Variables from                                    |
+//! |----------------------|---------------------------------------------------|
+//! | `"flake"`            | the devshell of `flake.nix` in the project root   |
+//! | `"flake:.#ci"`       | another devshell (relative to the project root)   |
+//! | `"envrc"`            | `direnv export json` in the project root          |
+//! | `"dotenv"`           | `.env` in the project root                        |
+//! | `"dotenv:.env.dev"`  | another dotenv file                               |
+//!
+//! Flake environments come from `nix print-dev-env` and are cached under
+//! `~/.local/share/zellij-welcome/devshells/`, keyed by the project and a
+//! hash of its `flake.lock`, so they're only rebuilt when the lock changes.
+//! [`warm`] fills the cache ahead of time (see [`crate::warm`]).
+
+use crate::catalog::{self, Catalog, Session};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+/// Variables `nix develop` leaves alone, as they describe the build sandbox
+/// rather than the shell
+const SANDBOX_VARS: [&str; 16] = [
+    "BASHOPTS",
+    "HOME",
+    "NIX_BUILD_TOP",
+    "NIX_ENFORCE_PURITY",
+    "NIX_LOG_FD",
+    "NIX_REMOTE",
+    "PPID",
+    "SHELL",
+    "SHELLOPTS",
+    "SSL_CERT_FILE",
+    "TEMP",
+    "TEMPDIR",
+    "TERM",
+    "TMP",
+    "TMPDIR",
+    "TZ",
+];
+
+enum Source {
+    Flake(String),
+    Envrc,
+    Dotenv(String),
+}
+
+fn parse_source(source: &str) -> Result<Source> {
+    let (kind, arg) = match source.split_once(':') {
+        Some((kind, arg)) => (kind, Some(arg.to_string())),
+        None => (source, None),
+    };
+    Ok(match kind {
+        "flake" => Source::Flake(arg.unwrap_or_else(|| ".".to_string())),
+        "envrc" => Source::Envrc,
+        "dotenv" => Source::Dotenv(arg.unwrap_or_else(|| ".env".to_string())),
+        _ => bail!("Unknown env_source `{}` (expected flake, envrc or dotenv)", source),
+    })
+}
+
+/// The variables to export into a new session of `session`; empty if it
+/// declares none.
+pub fn resolve(catalog: &Catalog, session: &Session) -> Result<Vec<(String, String)>> {
+    let mut vars = BTreeMap::new();
+    if let Some(source) = &session.env_source {
+        let dir = catalog
+            .project_root(session)
+            .with_context(|| format!("`{}` needs a cwd for its env_source", session.name))?;
+        match parse_source(source)? {
+            Source::Flake(installable) => vars.extend(flake_env(&dir, &installable)?),
+            Source::Envrc => vars.extend(envrc_env(&dir)?),
+            Source::Dotenv(file) => vars.extend(dotenv_env(&dir.join(file))?),
+        }
+    }
+    for (key, value) in &session.env {
+        let value = match value {
+            toml::Value::String(s) => s.clone(),
+            value => value.to_string(),
+        };
+        vars.insert(key.clone(), value);
+    }
+    Ok(vars.into_iter().collect())
+}
+
+fn flake_env(dir: &Path, installable: &str) -> Result<BTreeMap<String, String>> {
+    let cached = cache_path(dir, installable)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|source| serde_json::from_str(&source).ok());
+    match cached {
+        Some(vars) => Ok(with_path(vars)),
+        None => capture(dir, installable).map(with_path),
+    }
+}
+
+/// Reads the environment of devshell `installable` with `nix print-dev-env`
+/// and caches it for the current `flake.lock`.
+fn capture(dir: &Path, installable: &str) -> Result<BTreeMap<String, String>> {
+    let output = Command::new("nix")
+        .args(["print-dev-env", "--json", installable])
+        .current_dir(dir)
+        .output()
+        .context("Failed to run nix")?;
+    if !output.status.success() {
+        bail!(
+            "nix print-dev-env {} failed: {}",
+            installable,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("Failed to parse nix print-dev-env output")?;
+    let vars: BTreeMap<String, String> = json["variables"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(name, var)| var["type"] == "exported" && !SANDBOX_VARS.contains(&name.as_str()))
+        .filter_map(|(name, var)| Some((name.clone(), var["value"].as_str()?.to_string())))
+        .collect();
+
+    if let Some(cache) = cache_path(dir, installable) {
+        save_cache(&cache, &vars)?;
+    }
+    Ok(vars)
+}
+
+/// Builds devshell `installable` of the flake in `dir` the way its panes
+/// will, with `nix develop`, and caches its environment for new sessions.
+pub fn warm(dir: &Path, installable: &str) -> Result<()> {
+    let output = Command::new("nix")
+        .args(["develop", installable, "--command", "true"])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run nix")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or_default();
+        bail!("nix develop {} failed: {}", installable, last.trim());
+    }
+    capture(dir, installable)?;
+    Ok(())
+}
+
+/// The devshell's `PATH` goes before ours, like in `nix develop`.
+fn with_path(mut vars: BTreeMap<String, String>) -> BTreeMap<String, String> {
+    if let (Some(dev), Ok(ours)) = (vars.get("PATH"), std::env::var("PATH")) {
+        let path = format!("{}:{}", dev, ours);
+        vars.insert("PATH".to_string(), path);
+    }
+    vars
+}
+
+fn envrc_env(dir: &Path) -> Result<BTreeMap<String, String>> {
+    let output = Command::new("direnv")
+        .args(["export", "json"])
+        .current_dir(dir)
+        .output()
+        .context("Failed to run direnv")?;
+    if !output.status.success() {
+        bail!("direnv export failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    parse_direnv(&output.stdout)
+}
+
+/// The variables set by `direnv export json` output.
+fn parse_direnv(output: &[u8]) -> Result<BTreeMap<String, String>> {
+    // Empty when there is nothing to change
+    if output.iter().all(u8::is_ascii_whitespace) {
+        return Ok(BTreeMap::new());
+    }
+    // Unset variables come as null and are left out
+    let vars: BTreeMap<String, Option<String>> =
+        serde_json::from_slice(output).context("Failed to parse direnv output")?;
+    Ok(vars.into_iter().filter_map(|(k, v)| Some((k, v?))).collect())
+}
+
+fn dotenv_env(path: &Path) -> Result<BTreeMap<String, String>> {
+    let source = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(parse_dotenv(&source))
+}
+
+/// Parses `KEY=value` lines, allowing `export`, quotes and `#` comments.
+fn parse_dotenv(source: &str) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::new();
+    for line in source.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split(" #").next().unwrap_or_default().trim_end(),
+        };
+        vars.insert(key.trim().to_string(), value.to_string());
+    }
+    vars
+}
+
+/// Whether a project has a flake, and its devshell has been built.
+pub enum Devshell {
+    NoFlake,
+    NotBuilt,
+    /// Built, but `flake.lock` has changed since
+    Outdated,
+    Built(SystemTime),
+}
+
+/// The flake devshell `session` exports, `.` unless its `env_source` names
+/// another.
+pub fn installable(session: &Session) -> &str {
+    match session.env_source.as_deref().and_then(|s| s.strip_prefix("flake:")) {
+        Some(installable) => installable,
+        None => ".",
+    }
+}
+
+/// The state of devshell `installable` of the flake in `dir`, from the env
+/// cache or a nix-direnv profile.
+pub fn devshell(dir: &Path, installable: &str) -> Devshell {
+    if !dir.join("flake.nix").exists() {
+        return Devshell::NoFlake;
+    }
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    if let Some(built) = cache_path(dir, installable).as_deref().and_then(modified) {
+        return Devshell::Built(built);
+    }
+    // nix-direnv keeps the devshell as a GC root next to the flake
+    let direnv_profile = std::fs::read_dir(dir.join(".direnv")).ok().and_then(|entries| {
+        entries
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with("flake-profile"))
+            .filter(|e| e.path().exists())
+            .find_map(|e| modified(&e.path()))
+    });
+    if let Some(built) = direnv_profile {
+        return Devshell::Built(built);
+    }
+    let outdated = std::fs::read_dir(cache_dir())
+        .ok()
+        .is_some_and(|entries| entries.flatten().any(|e| e.file_name().to_string_lossy().starts_with(&cache_key(dir, installable))));
+    if outdated {
+        Devshell::Outdated
+    } else {
+        Devshell::NotBuilt
+    }
+}
+
+fn cache_dir() -> PathBuf {
+    catalog::data_dir().join("devshells")
+}
+
+/// `dir` and `installable` as a file name prefix, shared by the caches of
+/// all their lock files, e.g. `%home%me%dev%colony@9e3a…-`.
+fn cache_key(dir: &Path, installable: &str) -> String {
+    format!("{}@{:016x}-", dir.display().to_string().replace('/', "%"), fnv1a(installable.as_bytes()))
+}
+
+/// Where the environment of `installable` in `dir` is cached for the
+/// current `flake.lock`; `None` without a lock file to key it by.
+fn cache_path(dir: &Path, installable: &str) -> Option<PathBuf> {
+    let lock = std::fs::read(dir.join("flake.lock")).ok()?;
+    Some(cache_dir().join(format!("{}{:016x}.json", cache_key(dir, installable), fnv1a(&lock))))
+}
+
+/// 64-bit FNV-1a, which unlike std's hashers gives the same digest across
+/// Rust releases, so the cache survives rebuilds.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Stores `vars` at `path`, dropping the environments of the same
+/// installable from older lock files.
+fn save_cache(path: &Path, vars: &BTreeMap<String, String>) -> Result<()> {
+    let dir = cache_dir();
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let stale_prefix = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    // Up to the lock digest, which has no `-`
+    let stale_prefix = &stale_prefix[..stale_prefix.rfind('-').map_or(0, |i| i + 1)];
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(stale_prefix) {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+    std::fs::write(path, serde_json::to_string(vars)?).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn sources() {
+        assert!(matches!(parse_source("flake"), Ok(Source::Flake(i)) if i == "."));
+        assert!(matches!(parse_source("flake:.#ci"), Ok(Source::Flake(i)) if i == ".#ci"));
+        assert!(matches!(parse_source("envrc"), Ok(Source::Envrc)));
+        assert!(matches!(parse_source("dotenv"), Ok(Source::Dotenv(f)) if f == ".env"));
+        assert!(matches!(parse_source("dotenv:.env.dev"), Ok(Source::Dotenv(f)) if f == ".env.dev"));
+        assert!(parse_source("nix").is_err());
+    }
+
+    #[test]
+    fn dotenv() {
+        let source = r#"
+# Database
+export DATABASE_URL="postgres://localhost/colony" # trailing
+PORT = 8080 # comment
+GREETING='hello # world'
+HASH=a#b
+EMPTY=
+not a variable
+"#;
+        assert_eq!(
+            parse_dotenv(source),
+            vars(&[
+                ("DATABASE_URL", "postgres://localhost/colony"),
+                ("EMPTY", ""),
+                ("GREETING", "hello # world"),
+                ("HASH", "a#b"),
+                ("PORT", "8080"),
+            ])
+        );
+    }
+
+    #[test]
+    fn direnv() {
+        assert!(parse_direnv(b"\n").unwrap().is_empty());
+        assert_eq!(
+            parse_direnv(br#"{"FOO": "bar", "DIRENV_DIFF": null}"#).unwrap(),
+            vars(&[("FOO", "bar")])
+        );
+        assert!(parse_direnv(b"direnv: error").is_err());
+    }
+
+    #[test]
+    fn cache_keys_differ_by_installable() {
+        let dir = Path::new("/home/me/dev/colony");
+        let default = cache_key(dir, ".");
+        assert!(default.starts_with("%home%me%dev%colony@"));
+        assert!(default.ends_with('-'));
+        // The lock digest that follows has no `-`, so `save_cache` can cut it off
+        assert_eq!(default.matches('-').count(), 1);
+        assert_ne!(default, cache_key(dir, ".#ci"));
+    }
+}
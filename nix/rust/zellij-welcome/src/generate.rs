@@ -0,0 +1,262 @@
+//! `generate-layouts`: renders layouts from named templates plus per-session
+//! variables from the catalog.
+//!
+//! Templates are KDL files with `{{var}}` placeholders, looked up in
+//! `~/.config/zellij-welcome/templates/<name>.kdl` (and `<name>.swap.kdl`)
+//! before the built-in ones. Placeholders take dotted paths into the
+//! variables (`{{infra.0.command}}`) and are escaped for use inside a quoted
+//! string; `{{var|args}}` instead expands to a list of quoted arguments.
+
+use crate::catalog::{self, Catalog, Session};
+use crate::kdl;
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+use toml::{Table, Value};
+
+const BUILTIN_TEMPLATES: &[(&str, &str, &str)] = &[(
+    "project",
+    include_str!("../templates/project.kdl"),
+    include_str!("../templates/project.swap.kdl"),
+)];
+
+struct Template {
+    layout: String,
+    swap: Option<String>,
+}
+
+/// Generates (or with `check`, diffs) the layouts of all templated sessions,
+/// or only those in `names`. Returns false if `check` found differences.
+pub fn run(catalog: &Catalog, check: bool, names: &[String]) -> Result<bool> {
+    let sessions: Vec<&Session> = catalog
+        .sessions
+        .iter()
+        .filter(|s| s.template.is_some())
+        .filter(|s| names.is_empty() || names.contains(&s.name))
+        .collect();
+
+    for name in names {
+        if !sessions.iter().any(|s| &s.name == name) {
+            bail!("`{}` is not a catalog entry with a template", name);
+        }
+    }
+    if sessions.is_empty() {
+        println!("No catalog entries declare a template.");
+        return Ok(true);
+    }
+
+    let dir = catalog.layouts_dir();
+    let mut up_to_date = true;
+
+    for session in sessions {
+        let template_name = session.template.as_deref().unwrap_or_default();
+        let template = load_template(template_name)?;
+        let vars = variables(catalog, session);
+
+        let mut outputs = vec![(format!("{}.kdl", session.layout_name()), &template.layout)];
+        if let Some(swap) = &template.swap {
+            outputs.push((format!("{}.swap.kdl", session.layout_name()), swap));
+        }
+
+        for (file, source) in outputs {
+            let rendered = render(source, &vars)
+                .with_context(|| format!("Failed to render `{}` for {}", template_name, session.name))?;
+            kdl::parse(&rendered).map_err(|e| {
+                anyhow!("Template `{}` produced invalid KDL for {}: {}", template_name, file, e)
+            })?;
+
+            let path = dir.join(&file);
+            let current = std::fs::read_to_string(&path).unwrap_or_default();
+
+            if check {
+                if current != rendered {
+                    up_to_date = false;
+                    print!("{}", unified_diff(&current, &rendered, &catalog::contract_home(&path)));
+                }
+            } else if current != rendered {
+                std::fs::write(&path, &rendered)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                println!("wrote {}", catalog::contract_home(&path));
+            } else {
+                println!("unchanged {}", catalog::contract_home(&path));
+            }
+        }
+    }
+
+    if check && up_to_date {
+        println!("All generated layouts are up to date.");
+    }
+
+    Ok(up_to_date)
+}
+
+fn load_template(name: &str) -> Result<Template> {
+    let dir = catalog::config_dir().join("templates");
+    let path = dir.join(format!("{}.kdl", name));
+    if path.exists() {
+        let read = |p: &Path| {
+            std::fs::read_to_string(p).with_context(|| format!("Failed to read {}", p.display()))
+        };
+        let swap_path = dir.join(format!("{}.swap.kdl", name));
+        return Ok(Template {
+            layout: read(&path)?,
+            swap: if swap_path.exists() { Some(read(&swap_path)?) } else { None },
+        });
+    }
+
+    BUILTIN_TEMPLATES
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, layout, swap)| Template {
+            layout: layout.to_string(),
+            swap: Some(swap.to_string()),
+        })
+        .ok_or_else(|| anyhow!("Unknown template `{}` (looked in {})", name, dir.display()))
+}
+
+/// Catalog-wide `vars`, then `name`/`cwd`, then the session's own `vars`.
+fn variables(catalog: &Catalog, session: &Session) -> Table {
+    let mut vars = catalog.vars.clone();
+    vars.insert("name".into(), Value::String(session.name.clone()));
+    if let Some(cwd) = &session.cwd {
+        vars.insert("cwd".into(), Value::String(cwd.clone()));
+    }
+    for (k, v) in &session.vars {
+        vars.insert(k.clone(), v.clone());
+    }
+    vars
+}
+
+pub fn render(template: &str, vars: &Table) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| anyhow!("Unclosed `{{{{` in template"))?;
+        let expr = after[..end].trim();
+        rest = &after[end + 2..];
+
+        let (path, filter) = match expr.split_once('|') {
+            Some((path, filter)) => (path.trim(), Some(filter.trim())),
+            None => (expr, None),
+        };
+        let value = lookup(vars, path).ok_or_else(|| anyhow!("Undefined variable `{}`", path))?;
+
+        match filter {
+            None => out.push_str(&escape(&scalar(value, path)?)),
+            Some("args") => {
+                let args: Vec<String> = match value {
+                    Value::Array(items) => items
+                        .iter()
+                        .map(|v| scalar(v, path))
+                        .collect::<Result<_>>()?,
+                    v => scalar(v, path)?.split_whitespace().map(str::to_string).collect(),
+                };
+                let quoted: Vec<String> = args.iter().map(|a| format!("\"{}\"", escape(a))).collect();
+                out.push_str(&quoted.join(" "));
+            }
+            Some(other) => bail!("Unknown filter `{}` on `{}`", other, path),
+        }
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn lookup<'a>(vars: &'a Table, path: &str) -> Option<&'a Value> {
+    let mut parts = path.split('.');
+    let mut value = vars.get(parts.next()?)?;
+    for part in parts {
+        value = match value {
+            Value::Table(t) => t.get(part)?,
+            Value::Array(a) => a.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn scalar(value: &Value, path: &str) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(i) => Ok(i.to_string()),
+        Value::Float(f) => Ok(f.to_string()),
+        Value::Boolean(b) => Ok(b.to_string()),
+        _ => bail!("Variable `{}` is not a string", path),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Line-based unified diff with three lines of context.
+fn unified_diff(old: &str, new: &str, name: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence table, filled from the end
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // (tag, old line index, new line index, text)
+    let mut ops: Vec<(char, usize, usize, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', i, j, a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', i, j, a[i]));
+            i += 1;
+        } else {
+            ops.push(('+', i, j, b[j]));
+            j += 1;
+        }
+    }
+
+    const CONTEXT: usize = 3;
+    let mut out = format!("--- {} (on disk)\n+++ {} (generated)\n", name, name);
+    let changed: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != ' ').collect();
+    let mut k = 0;
+    while k < changed.len() {
+        let start = changed[k].saturating_sub(CONTEXT);
+        let mut end = (changed[k] + CONTEXT + 1).min(ops.len());
+        while k + 1 < changed.len() && changed[k + 1] <= end + CONTEXT {
+            k += 1;
+            end = (changed[k] + CONTEXT + 1).min(ops.len());
+        }
+
+        let hunk = &ops[start..end];
+        let old_len = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_len = hunk.iter().filter(|op| op.0 != '-').count();
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk[0].1 + 1,
+            old_len,
+            hunk[0].2 + 1,
+            new_len
+        ));
+        for (tag, _, _, text) in hunk {
+            out.push_str(&format!("{}{}\n", tag, text));
+        }
+        k += 1;
+    }
+    if changed.is_empty() {
+        out.push_str("\\ Files differ only in trailing whitespace\n");
+    }
+
+    out
+}
@@ -0,0 +1,237 @@
+//! Git state of the entries' working directories: branch, staged and dirty
+//! file counts, stashes, ahead/behind versus the upstream and the last
+//! commit. Only local refs are read (no fetch), and each repository is
+//! queried on a worker thread so a slow one never holds up the picker.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Repositories queried at the same time
+const WORKERS: usize = 4;
+
+#[derive(Clone)]
+pub struct Status {
+    /// Branch name, or `None` on a detached HEAD
+    pub branch: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub staged: u32,
+    /// Unstaged, untracked and conflicted files
+    pub dirty: u32,
+    pub stashes: u32,
+    pub last_commit: Option<Commit>,
+}
+
+#[derive(Clone)]
+pub struct Commit {
+    pub subject: String,
+    pub age: Duration,
+}
+
+impl Status {
+    /// Compact form for the list column, e.g. `main ↑1 ↓2 +3 ~4 ⚑1`.
+    pub fn summary(&self) -> String {
+        let mut summary = self.branch.clone().unwrap_or_else(|| "detached".to_string());
+        for (count, symbol) in [
+            (self.ahead, "↑"),
+            (self.behind, "↓"),
+            (self.staged, "+"),
+            (self.dirty, "~"),
+            (self.stashes, "⚑"),
+        ] {
+            if count > 0 {
+                summary.push_str(&format!(" {}{}", symbol, count));
+            }
+        }
+        summary
+    }
+}
+
+/// Statuses per working directory, filled in as the workers report back.
+pub struct Statuses {
+    statuses: HashMap<PathBuf, Option<Status>>,
+    /// Directories asked for that haven't reported yet
+    pending: HashSet<PathBuf>,
+    tx: Sender<(PathBuf, Option<Status>)>,
+    rx: Receiver<(PathBuf, Option<Status>)>,
+}
+
+impl Statuses {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            statuses: HashMap::new(),
+            pending: HashSet::new(),
+            tx,
+            rx,
+        }
+    }
+
+    /// Queries the directories not known or pending yet in the background.
+    pub fn request(&mut self, dirs: impl IntoIterator<Item = PathBuf>) {
+        let queue: Vec<PathBuf> = dirs
+            .into_iter()
+            .filter(|dir| !self.statuses.contains_key(dir) && self.pending.insert(dir.clone()))
+            .collect();
+        if queue.is_empty() {
+            return;
+        }
+
+        let workers = queue.len().min(WORKERS);
+        let queue = Arc::new(Mutex::new(queue));
+        for _ in 0..workers {
+            let queue = Arc::clone(&queue);
+            let tx = self.tx.clone();
+            std::thread::spawn(move || {
+                // Popping in a closure releases the lock before the query runs
+                let next = || queue.lock().ok().and_then(|mut q| q.pop());
+                while let Some(dir) = next() {
+                    let status = status(&dir);
+                    if tx.send((dir, status)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    /// Takes in what the workers found since the last call.
+    pub fn poll(&mut self) {
+        while let Ok((dir, status)) = self.rx.try_recv() {
+            self.pending.remove(&dir);
+            self.statuses.insert(dir, status);
+        }
+    }
+
+    /// The status of `dir`; `None` while pending or when it isn't a repository.
+    pub fn get(&self, dir: &Path) -> Option<&Status> {
+        self.statuses.get(dir).and_then(Option::as_ref)
+    }
+
+    pub fn is_pending(&self, dir: &Path) -> bool {
+        self.pending.contains(dir)
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        // Don't take the index lock away from the user's own git commands
+        .arg("--no-optional-locks")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads the status of the repository at `dir`, if it is one.
+pub fn status(dir: &Path) -> Option<Status> {
+    let porcelain = git(dir, &["status", "--porcelain=v2", "--branch"])?;
+    let mut status = parse_porcelain(&porcelain);
+    status.stashes = git(dir, &["stash", "list"]).map_or(0, |list| list.lines().count() as u32);
+    // Fails on a repository without commits
+    status.last_commit = git(dir, &["log", "-1", "--format=%ct%n%s"]).and_then(|log| parse_commit(&log, SystemTime::now()));
+    Some(status)
+}
+
+/// The branch and file counts in `git status --porcelain=v2 --branch`
+/// output.
+fn parse_porcelain(porcelain: &str) -> Status {
+    let mut status = Status {
+        branch: None,
+        upstream: None,
+        ahead: 0,
+        behind: 0,
+        staged: 0,
+        dirty: 0,
+        stashes: 0,
+        last_commit: None,
+    };
+
+    for line in porcelain.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            status.branch = (head != "(detached)").then(|| head.to_string());
+        } else if let Some(upstream) = line.strip_prefix("# branch.upstream ") {
+            status.upstream = Some(upstream.to_string());
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            // `+<ahead> -<behind>`
+            let mut counts = ab.split(' ').map(|n| n.get(1..).and_then(|n| n.parse().ok()).unwrap_or(0));
+            status.ahead = counts.next().unwrap_or(0);
+            status.behind = counts.next().unwrap_or(0);
+        } else if let Some(entry) = line.strip_prefix("1 ").or_else(|| line.strip_prefix("2 ")) {
+            // `XY`: X is the staged state, Y the worktree one, `.` unchanged
+            let mut xy = entry.chars();
+            if xy.next().is_some_and(|x| x != '.') {
+                status.staged += 1;
+            }
+            if xy.next().is_some_and(|y| y != '.') {
+                status.dirty += 1;
+            }
+        } else if line.starts_with("u ") || line.starts_with("? ") {
+            status.dirty += 1;
+        }
+    }
+
+    status
+}
+
+/// The commit in `git log -1 --format=%ct%n%s` output, aged as of `now`.
+fn parse_commit(log: &str, now: SystemTime) -> Option<Commit> {
+    let (time, subject) = log.trim_end().split_once('\n')?;
+    let time = UNIX_EPOCH + Duration::from_secs(time.parse().ok()?);
+    Some(Commit {
+        subject: subject.to_string(),
+        age: now.duration_since(time).unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn porcelain() {
+        let status = parse_porcelain(
+            "# branch.oid 1f2e3d4c5b6a\n\
+             # branch.head main\n\
+             # branch.upstream origin/main\n\
+             # branch.ab +2 -3\n\
+             1 M. N... 100644 100644 100644 aaa bbb src/main.rs\n\
+             1 MM N... 100644 100644 100644 aaa bbb src/git.rs\n\
+             1 .M N... 100644 100644 100644 aaa bbb README.md\n\
+             2 R. N... 100644 100644 100644 aaa bbb R100 new.rs\told.rs\n\
+             u UU N... 100644 100644 100644 100644 aaa bbb ccc conflict.rs\n\
+             ? notes.txt\n\
+             ! target/\n",
+        );
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.upstream.as_deref(), Some("origin/main"));
+        assert_eq!((status.ahead, status.behind), (2, 3));
+        assert_eq!((status.staged, status.dirty), (3, 4));
+        assert_eq!(status.summary(), "main ↑2 ↓3 +3 ~4");
+    }
+
+    #[test]
+    fn detached_without_upstream() {
+        let status = parse_porcelain("# branch.oid 1f2e3d4c5b6a\n# branch.head (detached)\n");
+        assert_eq!(status.branch, None);
+        assert_eq!(status.upstream, None);
+        assert_eq!(status.summary(), "detached");
+    }
+
+    #[test]
+    fn commits() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_060);
+        let commit = parse_commit("1000000\nFix the thing\n", now).unwrap();
+        assert_eq!(commit.subject, "Fix the thing");
+        assert_eq!(commit.age, Duration::from_secs(60));
+        assert!(parse_commit("", now).is_none());
+    }
+}
@@ -0,0 +1,225 @@
+//! Health of the local services catalog entries list under `services`: a
+//! TCP port is up when something accepts a connection on localhost, an HTTP
+//! URL when it answers with a 2xx or 3xx status. Probes run on worker
+//! threads with short timeouts and repeat every few seconds while the
+//! picker is open, so dev servers coming up or going down show quickly.
+
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Services probed at the same time
+const WORKERS: usize = 4;
+/// How long a connection or an HTTP answer may take
+const TIMEOUT: Duration = Duration::from_millis(500);
+/// Time between rounds of probes
+const REFRESH: Duration = Duration::from_secs(5);
+
+/// A port on localhost, or an `http://` health URL
+#[derive(Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(untagged)]
+pub enum Service {
+    Port(u16),
+    Url(String),
+}
+
+impl Service {
+    /// Short form for badges, e.g. `:5432` or `:3000/health`.
+    pub fn label(&self) -> String {
+        match self {
+            Service::Port(port) => format!(":{}", port),
+            Service::Url(url) => {
+                let rest = url.strip_prefix("http://").unwrap_or(url);
+                let rest = ["localhost", "127.0.0.1", "[::1]"]
+                    .iter()
+                    .find_map(|host| rest.strip_prefix(host))
+                    .unwrap_or(rest);
+                if rest.is_empty() {
+                    url.clone()
+                } else {
+                    rest.to_string()
+                }
+            }
+        }
+    }
+
+    pub fn display(&self) -> String {
+        match self {
+            Service::Port(port) => format!("localhost:{}", port),
+            Service::Url(url) => url.clone(),
+        }
+    }
+}
+
+/// The outcome of a probe
+#[derive(Clone)]
+pub struct Health {
+    pub up: bool,
+    /// The status line's code and reason, latency or error, for the preview
+    pub detail: String,
+}
+
+impl Health {
+    fn down(detail: impl Into<String>) -> Self {
+        Self {
+            up: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Probe results per service, filled in as the workers report back.
+pub struct Probes {
+    results: HashMap<Service, Health>,
+    /// Services asked for that haven't reported yet
+    pending: HashSet<Service>,
+    /// When the last round was requested
+    last: Option<Instant>,
+    tx: Sender<(Service, Health)>,
+    rx: Receiver<(Service, Health)>,
+}
+
+impl Probes {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            results: HashMap::new(),
+            pending: HashSet::new(),
+            last: None,
+            tx,
+            rx,
+        }
+    }
+
+    /// Probes the services not pending yet in the background. Earlier
+    /// results stay until the new ones are in.
+    pub fn request(&mut self, services: impl IntoIterator<Item = Service>) {
+        self.last = Some(Instant::now());
+        let queue: Vec<Service> = services
+            .into_iter()
+            .filter(|service| self.pending.insert(service.clone()))
+            .collect();
+        if queue.is_empty() {
+            return;
+        }
+
+        let workers = queue.len().min(WORKERS);
+        let queue = Arc::new(Mutex::new(queue));
+        for _ in 0..workers {
+            let queue = Arc::clone(&queue);
+            let tx = self.tx.clone();
+            std::thread::spawn(move || {
+                // Popping in a closure releases the lock before the probe runs
+                let next = || queue.lock().ok().and_then(|mut q| q.pop());
+                while let Some(service) = next() {
+                    let health = probe(&service);
+                    if tx.send((service, health)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    }
+
+    /// Takes in what the workers found since the last call.
+    pub fn poll(&mut self) {
+        while let Ok((service, health)) = self.rx.try_recv() {
+            self.pending.remove(&service);
+            self.results.insert(service, health);
+        }
+    }
+
+    /// Whether the last round is through and old enough to repeat.
+    pub fn is_due(&self) -> bool {
+        self.pending.is_empty() && self.last.is_none_or(|last| last.elapsed() >= REFRESH)
+    }
+
+    /// The last result for `service`; `None` before the first one is in.
+    pub fn get(&self, service: &Service) -> Option<&Health> {
+        self.results.get(service)
+    }
+}
+
+fn probe(service: &Service) -> Health {
+    let started = Instant::now();
+    let result = match service {
+        Service::Port(port) => connect("localhost", *port).map(|_| "open".to_string()),
+        Service::Url(url) => probe_url(url),
+    };
+    match result {
+        Ok(detail) => Health {
+            up: true,
+            detail: format!("{} · {}ms", detail, started.elapsed().as_millis()),
+        },
+        Err(health) => health,
+    }
+}
+
+/// Connects to the first address of `host` that accepts.
+fn connect(host: &str, port: u16) -> Result<TcpStream, Health> {
+    let addrs: Vec<SocketAddr> = if host == "localhost" {
+        // Dev servers often bind only one of them, whatever localhost resolves to
+        vec![
+            SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+            SocketAddr::from((Ipv6Addr::LOCALHOST, port)),
+        ]
+    } else {
+        (host, port)
+            .to_socket_addrs()
+            .map_err(|e| Health::down(format!("can't resolve {}: {}", host, e)))?
+            .collect()
+    };
+    let mut error = Health::down(format!("can't resolve {}", host));
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => error = Health::down("timed out"),
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => error = Health::down("connection refused"),
+            Err(e) => error = Health::down(e.to_string()),
+        }
+    }
+    Err(error)
+}
+
+/// Sends a `GET` for `url` and reads the status line, returning its code
+/// and reason if it's a 2xx or 3xx.
+fn probe_url(url: &str) -> Result<String, Health> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        return Err(Health::down("only http:// URLs are probed"));
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':').filter(|(_, port)| !port.ends_with(']')) {
+        Some((host, port)) => (host, port.parse().map_err(|_| Health::down(format!("bad port in {}", url)))?),
+        None => (authority, 80),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let mut stream = connect(host, port)?;
+    let _ = stream.set_read_timeout(Some(TIMEOUT));
+    let _ = stream.set_write_timeout(Some(TIMEOUT));
+    let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n", path, authority);
+    stream.write_all(request.as_bytes()).map_err(|e| Health::down(e.to_string()))?;
+
+    // The status line is all that's needed
+    let mut buffer = [0; 256];
+    let read = stream.read(&mut buffer).map_err(|e| match e.kind() {
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut => Health::down("no answer"),
+        _ => Health::down(e.to_string()),
+    })?;
+    let answer = String::from_utf8_lossy(&buffer[..read]);
+    let status = answer.lines().next().unwrap_or_default();
+    // `HTTP/1.1 200 OK`
+    let status = status.split_once(' ').map(|(_, status)| status.trim()).unwrap_or_default();
+    match status.split(' ').next().and_then(|code| code.parse::<u16>().ok()) {
+        Some(200..=399) => Ok(status.to_string()),
+        Some(_) => Err(Health::down(status)),
+        None => Err(Health::down("not an HTTP answer")),
+    }
+}
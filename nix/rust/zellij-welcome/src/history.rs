@@ -0,0 +1,112 @@
+//! Launch history, kept as JSON lines in
+//! `~/.local/share/zellij-welcome/history.jsonl`, and the frecency scores
+//! derived from it.
+
+use crate::catalog;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Oldest launches are dropped beyond this many
+const MAX_LAUNCHES: usize = 2000;
+
+/// A gap to the next launch longer than this is not counted as time spent
+const MAX_DURATION_SECS: i64 = 8 * 3600;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Launch {
+    pub entry: String,
+    pub instance: String,
+    /// Unix timestamp
+    pub timestamp: i64,
+    /// Time until the next launch, if that followed within a working session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct History {
+    pub launches: Vec<Launch>,
+}
+
+impl History {
+    /// Loads the history, skipping unreadable lines. A missing file is an
+    /// empty history.
+    pub fn load() -> Self {
+        let launches = std::fs::read_to_string(path())
+            .map(|source| {
+                source
+                    .lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { launches }
+    }
+
+    /// Appends a launch, filling in the duration of the previous one.
+    pub fn record(entry: &str, instance: &str) -> Result<()> {
+        let mut history = Self::load();
+        let now = chrono::Utc::now().timestamp();
+
+        if let Some(last) = history.launches.last_mut() {
+            let gap = now - last.timestamp;
+            if last.duration_secs.is_none() && (0..=MAX_DURATION_SECS).contains(&gap) {
+                last.duration_secs = Some(gap as u64);
+            }
+        }
+        history.launches.push(Launch {
+            entry: entry.to_string(),
+            instance: instance.to_string(),
+            timestamp: now,
+            duration_secs: None,
+        });
+        let excess = history.launches.len().saturating_sub(MAX_LAUNCHES);
+        history.launches.drain(..excess);
+
+        history.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        let mut out = String::new();
+        for launch in &self.launches {
+            out.push_str(&serde_json::to_string(launch)?);
+            out.push('\n');
+        }
+
+        // Write to a temporary file first so a crash never truncates the history
+        let tmp = path.with_extension("jsonl.tmp");
+        std::fs::write(&tmp, out).with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Frecency per entry: every launch counts, weighted by how recent it is.
+    pub fn frecency(&self) -> HashMap<String, f64> {
+        let now = chrono::Utc::now().timestamp();
+        let mut scores: HashMap<String, f64> = HashMap::new();
+        for launch in &self.launches {
+            let age_days = (now - launch.timestamp).max(0) / 86_400;
+            let weight = match age_days {
+                0..=3 => 100.0,
+                4..=13 => 70.0,
+                14..=30 => 50.0,
+                31..=90 => 30.0,
+                _ => 10.0,
+            };
+            *scores.entry(launch.entry.clone()).or_default() += weight;
+        }
+        scores
+    }
+}
+
+fn path() -> PathBuf {
+    catalog::data_dir().join("history.jsonl")
+}
@@ -0,0 +1,376 @@
+//! `pre_launch` and `post_launch` commands of catalog entries, run with
+//! `sh -c` in the entry's project root whenever a new session is created
+//! for it. They see `ZELLIJ_WELCOME_ENTRY`, `ZELLIJ_WELCOME_SESSION` (the
+//! instance name), `ZELLIJ_WELCOME_CWD` and `ZELLIJ_WELCOME_STAGE` (`pre` or
+//! `post`), on top of the session's environment (see [`env`](crate::env)).
+//! A failing pre-launch command aborts the launch.
+//!
+//! From the picker, their output streams into a panel, where Esc or Ctrl-C
+//! kills the running command and aborts the launch; the CLI prints it.
+
+use crate::catalog::{Catalog, Session};
+use crate::App;
+use anyhow::{bail, Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often a running hook is checked for having exited
+const OUTPUT_POLL: Duration = Duration::from_millis(50);
+/// How long output is still taken after a hook exited
+const OUTPUT_GRACE: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Stage {
+    Pre,
+    Post,
+}
+
+impl Stage {
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Pre => "pre",
+            Stage::Post => "post",
+        }
+    }
+
+    fn commands(self, session: &Session) -> &[String] {
+        match self {
+            Stage::Pre => &session.pre_launch,
+            Stage::Post => &session.post_launch,
+        }
+    }
+}
+
+/// What a hook run reports as it goes
+pub enum Event {
+    /// A command is starting
+    Command(Stage, String),
+    Stdout(String),
+    Stderr(String),
+}
+
+/// Prints hook output for the CLI: commands and stderr to stderr, the
+/// rest to stdout.
+pub fn print(event: Event) {
+    match event {
+        Event::Command(stage, command) => eprintln!("{}_launch $ {}", stage.name(), command),
+        Event::Stdout(line) => println!("{}", line),
+        Event::Stderr(line) => eprintln!("{}", line),
+    }
+}
+
+/// Lets the picker abort a launch while its hooks run. Clones share the
+/// state.
+#[derive(Clone)]
+pub struct Cancel(Option<Arc<Mutex<CancelState>>>);
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: bool,
+    /// The process group of the running command
+    running: Option<u32>,
+}
+
+impl Cancel {
+    /// One the picker can trip. Hooks run in process groups of their own,
+    /// so cancelling reaches everything they started.
+    pub fn new() -> Self {
+        Self(Some(Arc::default()))
+    }
+
+    /// For the command line, where nothing trips it: hooks stay in its
+    /// process group, so Ctrl-C in the terminal reaches them.
+    pub fn none() -> Self {
+        Self(None)
+    }
+
+    /// Kills the running command, if any, and fails the hooks from here on.
+    pub fn cancel(&self) {
+        let Some(Ok(mut state)) = self.0.as_ref().map(|s| s.lock()) else { return };
+        state.cancelled = true;
+        if let Some(group) = state.running.take() {
+            kill_group(group);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.as_ref().is_some_and(|s| s.lock().is_ok_and(|state| state.cancelled))
+    }
+
+    /// Records the command running in process group `group`, killing it
+    /// right away if the launch was cancelled meanwhile.
+    fn started(&self, group: u32) {
+        let Some(Ok(mut state)) = self.0.as_ref().map(|s| s.lock()) else { return };
+        if state.cancelled {
+            kill_group(group);
+        } else {
+            state.running = Some(group);
+        }
+    }
+
+    fn finished(&self) {
+        if let Some(Ok(mut state)) = self.0.as_ref().map(|s| s.lock()) {
+            state.running = None;
+        }
+    }
+}
+
+/// Sends `SIGTERM` to a command and everything it started.
+fn kill_group(group: u32) {
+    let _ = Command::new("kill")
+        .args(["-TERM", "--", &format!("-{}", group)])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Runs the `stage` commands of `session`, creating `instance`, one after
+/// another. Stops at the first one that fails, with its exit code and last
+/// line of stderr as the error, or once `cancel` is used.
+pub fn run(
+    catalog: &Catalog,
+    session: &Session,
+    instance: &str,
+    stage: Stage,
+    env: &[(String, String)],
+    report: &(dyn Fn(Event) + Sync),
+    cancel: &Cancel,
+) -> Result<()> {
+    let commands = stage.commands(session);
+    if commands.is_empty() {
+        return Ok(());
+    }
+    let dir = catalog
+        .project_root(session)
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(crate::catalog::home_dir);
+
+    for command in commands {
+        if cancel.is_cancelled() {
+            bail!("{}_launch cancelled", stage.name());
+        }
+        report(Event::Command(stage, command.clone()));
+        let mut child = Command::new("sh");
+        child
+            .arg("-c")
+            .arg(command)
+            .current_dir(&dir)
+            .envs(env.iter().map(|(k, v)| (k, v)))
+            .env("ZELLIJ_WELCOME_ENTRY", &session.name)
+            .env("ZELLIJ_WELCOME_SESSION", instance)
+            .env("ZELLIJ_WELCOME_CWD", &dir)
+            .env("ZELLIJ_WELCOME_STAGE", stage.name())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if cancel.0.is_some() {
+            child.process_group(0);
+        }
+        let mut child = child.spawn().with_context(|| format!("Failed to run `{}`", command))?;
+        cancel.started(child.id());
+
+        let status = follow(&mut child, report);
+        cancel.finished();
+        let (status, last_stderr) = status.with_context(|| format!("Failed to wait for `{}`", command))?;
+        if cancel.is_cancelled() {
+            bail!("{}_launch `{}` cancelled", stage.name(), command);
+        }
+        if !status.success() {
+            let code = status.code().map_or("a signal".to_string(), |c| c.to_string());
+            match last_stderr {
+                Some(line) => bail!("{}_launch `{}` exited with {}: {}", stage.name(), command, code, line),
+                None => bail!("{}_launch `{}` exited with {}", stage.name(), command, code),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reports the output of `child` until it exits, returning its status and
+/// last line of stderr. Processes it left running in the background (`foo
+/// &`) may hold on to its stdout and stderr, so the output is only followed
+/// for a moment after the exit rather than until the pipes close.
+fn follow(child: &mut Child, report: &(dyn Fn(Event) + Sync)) -> std::io::Result<(ExitStatus, Option<String>)> {
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        let tx = tx.clone();
+        std::thread::spawn(move || stream(stdout, |line| {
+            let _ = tx.send(Event::Stdout(line));
+        }));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let tx = tx.clone();
+        std::thread::spawn(move || stream(stderr, |line| {
+            let _ = tx.send(Event::Stderr(line));
+        }));
+    }
+    drop(tx);
+
+    let mut last_stderr = None;
+    let mut exited: Option<(ExitStatus, Instant)> = None;
+    loop {
+        match rx.recv_timeout(OUTPUT_POLL) {
+            Ok(event) => {
+                if let Event::Stderr(line) = &event {
+                    if !line.trim().is_empty() {
+                        last_stderr = Some(line.clone());
+                    }
+                }
+                report(event);
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        match exited {
+            Some((_, at)) if at.elapsed() >= OUTPUT_GRACE => break,
+            Some(_) => {}
+            None => exited = child.try_wait()?.map(|status| (status, Instant::now())),
+        }
+    }
+    let status = match exited {
+        Some((status, _)) => status,
+        None => child.wait()?,
+    };
+    Ok((status, last_stderr))
+}
+
+fn stream(source: impl Read, mut on_line: impl FnMut(String)) {
+    for line in BufReader::new(source).lines() {
+        match line {
+            Ok(line) => on_line(line),
+            Err(_) => break,
+        }
+    }
+}
+
+/// What the picker's panel receives from the launch thread
+pub enum Progress {
+    Event(Event),
+    Finished(Result<(), String>),
+}
+
+/// The panel showing a launch with hooks from the picker.
+pub struct Panel {
+    entry: String,
+    lines: Vec<Event>,
+    progress: Receiver<Progress>,
+    /// Shared with the launch thread
+    cancel: Cancel,
+    /// Set once the launch thread is done
+    outcome: Option<Result<(), String>>,
+}
+
+impl Panel {
+    pub fn new(entry: String, progress: Receiver<Progress>, cancel: Cancel) -> Self {
+        Self {
+            entry,
+            lines: Vec::new(),
+            progress,
+            cancel,
+            outcome: None,
+        }
+    }
+}
+
+/// Applies progress from the launch thread. Returns true once the launch
+/// went through, when the picker can close.
+pub fn poll(app: &mut App) -> bool {
+    let Some(panel) = &mut app.hooks else { return false };
+    while let Ok(progress) = panel.progress.try_recv() {
+        match progress {
+            Progress::Event(event) => panel.lines.push(event),
+            Progress::Finished(outcome) => panel.outcome = Some(outcome),
+        }
+    }
+    matches!(panel.outcome, Some(Ok(())))
+}
+
+/// Handles a key while the panel is open: a failed launch is dismissed
+/// with Enter or Esc; a running one is aborted with Esc or Ctrl-C, killing
+/// its hook.
+pub fn handle_key(app: &mut App, key: KeyEvent) {
+    let Some(panel) = &app.hooks else { return };
+    let interrupt = key.code == KeyCode::Esc
+        || (key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c'));
+    match &panel.outcome {
+        None if interrupt => {
+            panel.cancel.cancel();
+            app.status = Some(format!("{}: launch cancelled", panel.entry));
+            app.hooks = None;
+        }
+        Some(Err(_)) if interrupt || matches!(key.code, KeyCode::Enter | KeyCode::Char('q')) => {
+            if let Some(Panel { outcome: Some(Err(e)), entry, .. }) = app.hooks.take() {
+                app.status = Some(format!("{}: {}", entry, e));
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn render(f: &mut Frame, panel: &Panel, area: Rect) {
+    let key = Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let width = area.width.saturating_sub(8).clamp(30.min(area.width), 100);
+    let height = area.height.saturating_sub(4).clamp(8.min(area.height), 24);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let mut footer = vec![Line::from("")];
+    match &panel.outcome {
+        None => footer.push(Line::from(vec![
+            Span::styled("running…  ", dim),
+            Span::styled("esc", key),
+            Span::styled(" cancel", dim),
+        ])),
+        Some(Ok(())) => footer.push(Line::from(Span::styled("✓ launched", Style::default().fg(Color::Green)))),
+        Some(Err(e)) => {
+            footer.push(Line::from(Span::styled(format!("✗ {}", e), Style::default().fg(Color::Red))));
+            footer.push(Line::from(vec![Span::styled("⏎", key), Span::styled(" close", dim)]));
+        }
+    }
+
+    // The latest output that fits above the footer
+    let room = (height as usize).saturating_sub(2 + footer.len());
+    let skip = panel.lines.len().saturating_sub(room);
+    let mut lines: Vec<Line> = panel.lines[skip..]
+        .iter()
+        .map(|event| match event {
+            Event::Command(stage, command) => Line::from(vec![
+                Span::styled(format!("{}_launch $ ", stage.name()), dim),
+                Span::styled(command.clone(), key),
+            ]),
+            Event::Stdout(line) => Line::from(Span::styled(line.clone(), Style::default().fg(Color::White))),
+            Event::Stderr(line) => Line::from(Span::styled(line.clone(), Style::default().fg(Color::Red))),
+        })
+        .collect();
+    lines.extend(footer);
+
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Blue))
+                .title(format!(" Launching {} ", panel.entry))
+                .title_style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+        ),
+        popup,
+    );
+}
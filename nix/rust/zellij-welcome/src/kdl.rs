@@ -0,0 +1,621 @@
+//! Minimal KDL (v1) reader, just enough for zellij layout files.
+//!
+//! Supports nodes with arguments, `key=value` properties and child blocks,
+//! quoted and raw strings, numbers, booleans, `null`, line/block comments,
+//! slashdash (`/-`) comments, type annotations (ignored) and `\` line
+//! continuations.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{}", s),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Null => write!(f, "null"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub name: String,
+    pub args: Vec<Value>,
+    pub props: Vec<(String, Value)>,
+    pub children: Vec<Node>,
+    /// 1-based line the node starts on
+    pub line: usize,
+}
+
+impl Node {
+    pub fn prop(&self, key: &str) -> Option<&Value> {
+        // Later properties override earlier ones, as in the KDL spec
+        self.props.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn prop_str(&self, key: &str) -> Option<&str> {
+        self.prop(key).and_then(Value::as_str)
+    }
+
+    pub fn prop_bool(&self, key: &str) -> bool {
+        self.prop(key).and_then(Value::as_bool).unwrap_or(false)
+    }
+
+    pub fn first_arg_str(&self) -> Option<&str> {
+        self.args.first().and_then(Value::as_str)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Node> {
+        self.children.iter().find(|n| n.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Node> + 'a {
+        self.children.iter().filter(move |n| n.name == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn parse(input: &str) -> Result<Vec<Node>, ParseError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+        col: 1,
+    };
+    let nodes = parser.nodes(false)?;
+    Ok(nodes)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    col: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line: self.line,
+            col: self.col,
+            message: message.into(),
+        }
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.peek_at(i) == Some(c))
+    }
+
+    /// Skips spaces, comments and escaped newlines, but not plain newlines.
+    fn skip_inline_ws(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() && c != '\n' => {
+                    self.bump();
+                }
+                Some('\\') => {
+                    self.bump();
+                    self.skip_inline_ws()?;
+                    match self.peek() {
+                        Some('\n') => {
+                            self.bump();
+                        }
+                        None => {}
+                        _ => return Err(self.error("expected newline after line continuation")),
+                    }
+                }
+                Some('/') if self.peek_at(1) == Some('*') => self.skip_block_comment()?,
+                Some('/') if self.peek_at(1) == Some('/') => {
+                    // Line comment: leave the newline in place as a terminator
+                    while let Some(c) = self.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        self.bump();
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skips all whitespace, including newlines and semicolons between nodes.
+    fn skip_line_ws(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.skip_inline_ws()?;
+            match self.peek() {
+                Some('\n') | Some(';') => {
+                    self.bump();
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn skip_block_comment(&mut self) -> Result<(), ParseError> {
+        let (line, col) = (self.line, self.col);
+        self.bump();
+        self.bump();
+        let mut depth = 1;
+        while depth > 0 {
+            if self.starts_with("/*") {
+                self.bump();
+                self.bump();
+                depth += 1;
+            } else if self.starts_with("*/") {
+                self.bump();
+                self.bump();
+                depth -= 1;
+            } else if self.bump().is_none() {
+                return Err(ParseError {
+                    line,
+                    col,
+                    message: "unterminated block comment".into(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn nodes(&mut self, nested: bool) -> Result<Vec<Node>, ParseError> {
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_line_ws()?;
+            match self.peek() {
+                None if nested => return Err(self.error("unexpected end of file, expected '}'")),
+                None => return Ok(nodes),
+                Some('}') if nested => return Ok(nodes),
+                Some('}') => return Err(self.error("unexpected '}'")),
+                _ => {}
+            }
+
+            let slashdash = self.take_slashdash()?;
+            let node = self.node()?;
+            if !slashdash {
+                nodes.push(node);
+            }
+        }
+    }
+
+    fn take_slashdash(&mut self) -> Result<bool, ParseError> {
+        if self.starts_with("/-") {
+            self.bump();
+            self.bump();
+            self.skip_line_ws()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn node(&mut self) -> Result<Node, ParseError> {
+        self.skip_type_annotation()?;
+        let line = self.line;
+        let name = self.identifier_or_string()?;
+        let mut node = Node {
+            name,
+            args: Vec::new(),
+            props: Vec::new(),
+            children: Vec::new(),
+            line,
+        };
+
+        loop {
+            let had_space = self.peek().is_some_and(|c| c.is_whitespace() || c == '\\' || c == '/');
+            self.skip_inline_ws()?;
+            match self.peek() {
+                None | Some('\n') | Some(';') => {
+                    if self.peek().is_some() {
+                        self.bump();
+                    }
+                    return Ok(node);
+                }
+                Some('}') => return Ok(node),
+                _ => {}
+            }
+
+            let slashdash = self.take_slashdash()?;
+
+            if self.peek() == Some('{') {
+                self.bump();
+                let children = self.nodes(true)?;
+                self.bump(); // closing brace
+                if !slashdash {
+                    node.children.extend(children);
+                }
+                continue;
+            }
+
+            if !had_space && !slashdash {
+                return Err(self.error("expected whitespace between node entries"));
+            }
+
+            self.skip_type_annotation()?;
+            let entry_start = (self.line, self.col);
+            let token = self.entry_token()?;
+            let entry = match token {
+                Token::Ident(key) if self.peek() == Some('=') => {
+                    self.bump();
+                    self.skip_type_annotation()?;
+                    let value = match self.entry_token()? {
+                        Token::Value(v) => v,
+                        Token::Ident(word) => {
+                            return Err(self.error(format!("invalid value '{}'", word)))
+                        }
+                    };
+                    Entry::Prop(key, value)
+                }
+                Token::Value(Value::String(key)) if self.peek() == Some('=') => {
+                    self.bump();
+                    self.skip_type_annotation()?;
+                    let value = match self.entry_token()? {
+                        Token::Value(v) => v,
+                        Token::Ident(word) => {
+                            return Err(self.error(format!("invalid value '{}'", word)))
+                        }
+                    };
+                    Entry::Prop(key, value)
+                }
+                Token::Value(v) => Entry::Arg(v),
+                Token::Ident(word) => {
+                    return Err(ParseError {
+                        line: entry_start.0,
+                        col: entry_start.1,
+                        message: format!("invalid value '{}' (strings must be quoted)", word),
+                    })
+                }
+            };
+
+            if !slashdash {
+                match entry {
+                    Entry::Arg(v) => node.args.push(v),
+                    Entry::Prop(k, v) => node.props.push((k, v)),
+                }
+            }
+        }
+    }
+
+    fn skip_type_annotation(&mut self) -> Result<(), ParseError> {
+        if self.peek() != Some('(') {
+            return Ok(());
+        }
+        self.bump();
+        self.identifier_or_string()?;
+        if self.bump() != Some(')') {
+            return Err(self.error("expected ')' to close type annotation"));
+        }
+        Ok(())
+    }
+
+    fn identifier_or_string(&mut self) -> Result<String, ParseError> {
+        match self.entry_token()? {
+            Token::Ident(s) => Ok(s),
+            Token::Value(Value::String(s)) => Ok(s),
+            Token::Value(v) => Err(self.error(format!("expected identifier, found '{}'", v))),
+        }
+    }
+
+    fn entry_token(&mut self) -> Result<Token, ParseError> {
+        match self.peek() {
+            Some('"') => self.quoted_string().map(|s| Token::Value(Value::String(s))),
+            Some('r') if matches!(self.peek_at(1), Some('"') | Some('#')) => {
+                self.raw_string().map(|s| Token::Value(Value::String(s)))
+            }
+            Some(c) if is_ident_char(c) => {
+                let word = self.bare_word();
+                Ok(classify_word(word))
+            }
+            Some(c) => Err(self.error(format!("unexpected character '{}'", c))),
+            None => Err(self.error("unexpected end of file")),
+        }
+    }
+
+    fn bare_word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if !is_ident_char(c) {
+                break;
+            }
+            word.push(c);
+            self.bump();
+        }
+        word
+    }
+
+    fn quoted_string(&mut self) -> Result<String, ParseError> {
+        let (line, col) = (self.line, self.col);
+        self.bump();
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None => {
+                    return Err(ParseError {
+                        line,
+                        col,
+                        message: "unterminated string".into(),
+                    })
+                }
+                Some('"') => return Ok(out),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('"') => '"',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => self.unicode_escape()?,
+                        Some(c) => return Err(self.error(format!("invalid escape '\\{}'", c))),
+                        None => continue,
+                    };
+                    out.push(escaped);
+                }
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        if self.bump() != Some('{') {
+            return Err(self.error("expected '{' in unicode escape"));
+        }
+        let mut hex = String::new();
+        while let Some(c) = self.bump() {
+            if c == '}' {
+                break;
+            }
+            hex.push(c);
+        }
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(format!("invalid unicode escape '{}'", hex)))
+    }
+
+    fn raw_string(&mut self) -> Result<String, ParseError> {
+        let (line, col) = (self.line, self.col);
+        self.bump(); // r
+        let mut hashes = 0;
+        while self.peek() == Some('#') {
+            self.bump();
+            hashes += 1;
+        }
+        if self.bump() != Some('"') {
+            return Err(self.error("expected '\"' in raw string"));
+        }
+        let terminator: String = std::iter::once('"').chain(std::iter::repeat_n('#', hashes)).collect();
+        let mut out = String::new();
+        loop {
+            if self.starts_with(&terminator) {
+                for _ in 0..terminator.chars().count() {
+                    self.bump();
+                }
+                return Ok(out);
+            }
+            match self.bump() {
+                Some(c) => out.push(c),
+                None => {
+                    return Err(ParseError {
+                        line,
+                        col,
+                        message: "unterminated raw string".into(),
+                    })
+                }
+            }
+        }
+    }
+}
+
+enum Token {
+    Ident(String),
+    Value(Value),
+}
+
+enum Entry {
+    Arg(Value),
+    Prop(String, Value),
+}
+
+fn is_ident_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '\\' | '/' | '(' | ')' | '{' | '}' | '<' | '>' | ';' | '[' | ']' | '=' | ',' | '"')
+}
+
+fn classify_word(word: String) -> Token {
+    match word.as_str() {
+        "true" => return Token::Value(Value::Bool(true)),
+        "false" => return Token::Value(Value::Bool(false)),
+        "null" => return Token::Value(Value::Null),
+        _ => {}
+    }
+
+    let digits = word.strip_prefix(['-', '+']).unwrap_or(&word);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return Token::Ident(word);
+    }
+
+    let cleaned: String = word.chars().filter(|&c| c != '_').collect();
+    let radix = [("0x", 16), ("0o", 8), ("0b", 2)]
+        .into_iter()
+        .find(|(prefix, _)| digits.starts_with(prefix));
+    if let Some((prefix, radix)) = radix {
+        let negative = cleaned.starts_with('-');
+        let body = cleaned.trim_start_matches(['-', '+']).trim_start_matches(prefix);
+        if let Ok(n) = i64::from_str_radix(body, radix) {
+            return Token::Value(Value::Int(if negative { -n } else { n }));
+        }
+    } else if let Ok(n) = cleaned.parse::<i64>() {
+        return Token::Value(Value::Int(n));
+    } else if let Ok(x) = cleaned.parse::<f64>() {
+        return Token::Value(Value::Float(x));
+    }
+
+    Token::Ident(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one(input: &str) -> Node {
+        let mut nodes = parse(input).unwrap();
+        assert_eq!(nodes.len(), 1, "expected one node in {:?}", input);
+        nodes.remove(0)
+    }
+
+    fn parse_err(input: &str) -> (usize, usize, String) {
+        let e = parse(input).unwrap_err();
+        (e.line, e.col, e.message)
+    }
+
+    #[test]
+    fn strings_and_escapes() {
+        let node = parse_one(r#"pane "a\"b" "tab\there" "line\nbreak" "\\ \/ \u{1F600}""#);
+        assert_eq!(
+            node.args,
+            vec![
+                Value::String("a\"b".into()),
+                Value::String("tab\there".into()),
+                Value::String("line\nbreak".into()),
+                Value::String("\\ / 😀".into()),
+            ]
+        );
+        // Quoted names are names like any other
+        assert_eq!(parse_one(r#""my node" 1"#).name, "my node");
+    }
+
+    #[test]
+    fn raw_strings() {
+        let node = parse_one(r###"cmd r"C:\path" r#"say "hi""# r##"a "# b"##"###);
+        assert_eq!(
+            node.args,
+            vec![
+                Value::String(r"C:\path".into()),
+                Value::String(r#"say "hi""#.into()),
+                Value::String(r##"a "# b"##.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn slashdash_comments() {
+        let nodes = parse("/-skipped 1 {\n  child\n}\nkept /-2 3 /-key=4 /-{\n  gone\n}\n").unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].name, "kept");
+        assert_eq!(nodes[0].args, vec![Value::Int(3)]);
+        assert!(nodes[0].props.is_empty());
+        assert!(nodes[0].children.is_empty());
+    }
+
+    #[test]
+    fn comments_and_continuations() {
+        let nodes = parse("// line\na /* block /* nested */ */ 1 \\\n  2; b\n").unwrap();
+        let names: Vec<&str> = nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(nodes[0].args, vec![Value::Int(1), Value::Int(2)]);
+    }
+
+    #[test]
+    fn props_and_args() {
+        let node = parse_one(r#"pane 1 -2.5 0x1f true null size="50%" (type)name="x" focus=true size=10"#);
+        assert_eq!(
+            node.args,
+            vec![Value::Int(1), Value::Float(-2.5), Value::Int(31), Value::Bool(true), Value::Null]
+        );
+        assert_eq!(node.prop_str("name"), Some("x"));
+        assert!(node.prop_bool("focus"));
+        // The later of two properties wins
+        assert_eq!(node.prop("size"), Some(&Value::Int(10)));
+        assert_eq!(node.props.len(), 4);
+    }
+
+    #[test]
+    fn nested_children() {
+        let input = "layout {\n    tab name=\"one\" {\n        pane\n        pane { pane; pane }\n    }\n    tab\n}\n";
+        let layout = parse_one(input);
+        assert_eq!(layout.children_named("tab").count(), 2);
+        let tab = layout.child("tab").unwrap();
+        assert_eq!(tab.prop_str("name"), Some("one"));
+        assert_eq!(tab.line, 2);
+        assert_eq!(tab.children.len(), 2);
+        assert_eq!(tab.children[1].children.len(), 2);
+        assert_eq!(tab.children[1].line, 4);
+    }
+
+    #[test]
+    fn error_positions() {
+        // Unterminated strings and comments point at where they start
+        assert_eq!(parse_err("a\nb \"open"), (2, 3, "unterminated string".into()));
+        assert_eq!(parse_err("a r#\"open"), (1, 3, "unterminated raw string".into()));
+        assert_eq!(parse_err("a /* open"), (1, 3, "unterminated block comment".into()));
+        // Bare words point at the word
+        assert_eq!(parse_err("pane   bare"), (1, 8, "invalid value 'bare' (strings must be quoted)".into()));
+        // The rest point at where parsing stopped
+        assert_eq!(parse_err("a {\n  b\n}\n}"), (4, 1, "unexpected '}'".into()));
+        assert_eq!(parse_err("a {\n b"), (2, 3, "unexpected end of file, expected '}'".into()));
+        assert_eq!(parse_err("a \"\\q\""), (1, 6, "invalid escape '\\q'".into()));
+        assert_eq!(parse("a\n  \"x").unwrap_err().to_string(), "2:3: unterminated string");
+    }
+}
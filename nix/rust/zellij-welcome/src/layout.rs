@@ -0,0 +1,236 @@
+//! Reads zellij layout files into a summary of their tabs, pane commands and
+//! pane split trees.
+
+use crate::kdl::{self, Node, Value};
+use std::path::Path;
+
+pub struct LayoutInfo {
+    pub cwd: Option<String>,
+    pub tabs: Vec<TabInfo>,
+    pub swap_layouts: Vec<SwapLayout>,
+}
+
+pub struct TabInfo {
+    pub name: String,
+    pub cwd: Option<String>,
+    pub commands: Vec<PaneCommand>,
+    /// The tab's panes, wrapped in `default_tab_template` if there is one
+    pub root: Pane,
+}
+
+pub struct SwapLayout {
+    pub name: String,
+    pub floating: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SplitDirection {
+    /// Children are laid out top to bottom (zellij's default)
+    Horizontal,
+    /// Children are laid out side by side
+    Vertical,
+}
+
+#[derive(Clone, Copy)]
+pub enum PaneSize {
+    Fixed(u16),
+    Percent(u16),
+}
+
+#[derive(Clone)]
+pub struct Pane {
+    pub label: Option<String>,
+    pub size: Option<PaneSize>,
+    pub split: SplitDirection,
+    pub stacked: bool,
+    pub children: Vec<Pane>,
+}
+
+pub struct PaneCommand {
+    pub name: Option<String>,
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl PaneCommand {
+    /// Short human-readable form, unwrapping `nix develop <path> --command ...`
+    /// so that e.g. colony's panes read as `make init` rather than the full
+    /// nix invocation.
+    pub fn display(&self) -> String {
+        if self.command == "nix" && self.args.first().map(String::as_str) == Some("develop") {
+            if let Some(pos) = self.args.iter().position(|a| a == "--command" || a == "-c") {
+                return self.args[pos + 1..].join(" ");
+            }
+        }
+
+        // Absolute paths like `/Users/cgpp/.local/share/bob/nvim-bin/nvim` read as `nvim`
+        let program = match Path::new(&self.command).file_name() {
+            Some(name) if self.command.starts_with('/') => name.to_string_lossy().into_owned(),
+            _ => self.command.clone(),
+        };
+
+        if self.args.is_empty() {
+            program
+        } else {
+            format!("{} {}", program, self.args.join(" "))
+        }
+    }
+}
+
+pub fn load(path: &Path) -> Result<LayoutInfo, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let nodes = kdl::parse(&source).map_err(|e| e.to_string())?;
+
+    let root = nodes
+        .iter()
+        .find(|n| n.name == "layout")
+        .ok_or_else(|| "no top-level `layout` node".to_string())?;
+
+    let template = root.child("default_tab_template");
+
+    let tabs: Vec<TabInfo> = root
+        .children_named("tab")
+        .enumerate()
+        .map(|(i, tab)| TabInfo {
+            name: tab
+                .prop_str("name")
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("Tab #{}", i + 1)),
+            cwd: node_cwd(tab),
+            commands: collect_commands(tab),
+            root: tab_tree(tab, template),
+        })
+        .collect();
+
+    // Layouts without explicit tabs describe a single implicit one
+    let tabs = if tabs.is_empty() {
+        vec![TabInfo {
+            name: "Tab #1".to_string(),
+            cwd: None,
+            commands: collect_commands(root),
+            root: pane_tree(root, None),
+        }]
+    } else {
+        tabs
+    };
+
+    // Swap layouts live next to the layout in `<name>.swap.kdl`, or inline
+    let mut swap_layouts = swap_layouts_in(root);
+    let swap_source = std::fs::read_to_string(path.with_extension("swap.kdl")).ok();
+    if let Some(nodes) = swap_source.and_then(|s| kdl::parse(&s).ok()) {
+        swap_layouts.extend(nodes.iter().filter_map(swap_layout));
+    }
+
+    Ok(LayoutInfo {
+        cwd: node_cwd(root),
+        tabs,
+        swap_layouts,
+    })
+}
+
+fn swap_layouts_in(node: &Node) -> Vec<SwapLayout> {
+    node.children.iter().filter_map(swap_layout).collect()
+}
+
+fn swap_layout(node: &Node) -> Option<SwapLayout> {
+    let floating = match node.name.as_str() {
+        "swap_tiled_layout" => false,
+        "swap_floating_layout" => true,
+        _ => return None,
+    };
+    Some(SwapLayout {
+        name: node.prop_str("name").unwrap_or("unnamed").to_string(),
+        floating,
+    })
+}
+
+/// Builds a tab's pane tree, substituting it for `children` in the template.
+fn tab_tree(tab: &Node, template: Option<&Node>) -> Pane {
+    let tab_root = pane_tree(tab, None);
+    match template {
+        Some(template) => pane_tree(template, Some(&tab_root)),
+        None => tab_root,
+    }
+}
+
+/// Converts a `pane` (or tab/template container) node into a [`Pane`].
+/// A `children` placeholder is replaced by `slot`, when given.
+fn pane_tree(node: &Node, slot: Option<&Pane>) -> Pane {
+    let label = node
+        .prop_str("name")
+        .map(str::to_string)
+        .or_else(|| pane_command(node).map(|c| c.display()))
+        .or_else(|| {
+            node.child("plugin")
+                .and_then(|p| p.prop_str("location"))
+                .map(str::to_string)
+        });
+
+    let size = node.prop("size").and_then(|v| match v {
+        Value::Int(n) => u16::try_from(*n).ok().map(PaneSize::Fixed),
+        Value::String(s) => match s.strip_suffix('%') {
+            Some(pct) => pct.trim().parse().ok().map(PaneSize::Percent),
+            None => s.trim().parse().ok().map(PaneSize::Fixed),
+        },
+        _ => None,
+    });
+
+    let split = match node.prop_str("split_direction") {
+        Some(dir) if dir.eq_ignore_ascii_case("vertical") => SplitDirection::Vertical,
+        _ => SplitDirection::Horizontal,
+    };
+
+    let children = node
+        .children
+        .iter()
+        .filter_map(|child| match child.name.as_str() {
+            "pane" => Some(pane_tree(child, slot)),
+            "children" => slot.cloned(),
+            _ => None,
+        })
+        .collect();
+
+    Pane {
+        label,
+        size,
+        split,
+        stacked: node.prop_bool("stacked"),
+        children,
+    }
+}
+
+/// `cwd` may be given either as a property or as a child node.
+pub fn node_cwd(node: &Node) -> Option<String> {
+    node.prop_str("cwd")
+        .or_else(|| node.child("cwd").and_then(Node::first_arg_str))
+        .map(str::to_string)
+}
+
+/// Returns the command a pane runs, from either `command=` or a `command` child.
+pub fn pane_command(pane: &Node) -> Option<PaneCommand> {
+    let command = pane
+        .prop_str("command")
+        .or_else(|| pane.child("command").and_then(Node::first_arg_str))?;
+
+    let args = pane
+        .child("args")
+        .map(|a| a.args.iter().map(|v| v.to_string()).collect())
+        .unwrap_or_default();
+
+    Some(PaneCommand {
+        name: pane.prop_str("name").map(str::to_string),
+        command: command.to_string(),
+        args,
+    })
+}
+
+fn collect_commands(node: &Node) -> Vec<PaneCommand> {
+    let mut out = Vec::new();
+    for child in node.children_named("pane") {
+        if let Some(cmd) = pane_command(child) {
+            out.push(cmd);
+        }
+        out.extend(collect_commands(child));
+    }
+    out
+}
@@ -0,0 +1,286 @@
+//! `lint-layouts`: checks the layouts directory for problems that only show
+//! up once a session is created, like paths that only exist on another host.
+
+use crate::catalog::{self, Catalog};
+use crate::kdl::{self, Node, Value};
+use crate::layout;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IssueKind {
+    Syntax,
+    MissingPath,
+    MissingCommand,
+    MissingSwap,
+    MissingLayout,
+    Unreferenced,
+}
+
+impl IssueKind {
+    fn label(self) -> &'static str {
+        match self {
+            IssueKind::Syntax => "syntax",
+            IssueKind::MissingPath => "missing-path",
+            IssueKind::MissingCommand => "missing-command",
+            IssueKind::MissingSwap => "missing-swap",
+            IssueKind::MissingLayout => "missing-layout",
+            IssueKind::Unreferenced => "unreferenced",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Issue {
+    /// Layout file name, or `catalog` for catalog-level problems
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+/// Lints every layout and prints the result. Returns the number of issues.
+pub fn run(catalog: &Catalog, json: bool) -> Result<usize> {
+    let issues = lint(catalog)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues)?);
+    } else {
+        print_human(&issues);
+    }
+
+    Ok(issues.len())
+}
+
+pub fn lint(catalog: &Catalog) -> Result<Vec<Issue>> {
+    let dir = catalog.layouts_dir();
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read layouts dir {}", dir.display()))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "kdl"))
+        .collect();
+    files.sort();
+
+    let mut issues = Vec::new();
+    let mut layout_names = BTreeSet::new();
+    let mut swap_names = BTreeSet::new();
+
+    for path in &files {
+        let file = file_name(path);
+        let (stem, is_swap) = match file.strip_suffix(".swap.kdl") {
+            Some(stem) => (stem.to_string(), true),
+            None => (file.trim_end_matches(".kdl").to_string(), false),
+        };
+        if is_swap {
+            swap_names.insert(stem);
+        } else {
+            layout_names.insert(stem);
+        }
+
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let nodes = match kdl::parse(&source) {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                issues.push(Issue {
+                    file,
+                    line: Some(e.line),
+                    kind: IssueKind::Syntax,
+                    message: format!("column {}: {}", e.col, e.message),
+                });
+                continue;
+            }
+        };
+
+        if !is_swap && !nodes.iter().any(|n| n.name == "layout") {
+            issues.push(Issue {
+                file: file.clone(),
+                line: None,
+                kind: IssueKind::Syntax,
+                message: "no top-level `layout` node".to_string(),
+            });
+        }
+
+        let mut checker = Checker { file: &file, issues: &mut issues };
+        for node in &nodes {
+            checker.walk(node, None);
+        }
+    }
+
+    for name in &layout_names {
+        if !swap_names.contains(name) {
+            issues.push(Issue {
+                file: format!("{}.kdl", name),
+                line: None,
+                kind: IssueKind::MissingSwap,
+                message: format!("no `{}.swap.kdl` companion", name),
+            });
+        }
+    }
+    for name in swap_names.difference(&layout_names) {
+        issues.push(Issue {
+            file: format!("{}.swap.kdl", name),
+            line: None,
+            kind: IssueKind::MissingSwap,
+            message: format!("swap layout without a `{}.kdl`", name),
+        });
+    }
+
+    for session in &catalog.sessions {
+        if !layout_names.contains(session.layout_name()) {
+            issues.push(Issue {
+                file: "catalog".to_string(),
+                line: None,
+                kind: IssueKind::MissingLayout,
+                message: format!(
+                    "`{}` has no layout ({})",
+                    session.name,
+                    catalog::contract_home(&catalog.layout_path(session))
+                ),
+            });
+        }
+    }
+    for name in &layout_names {
+        if !catalog.sessions.iter().any(|s| s.layout_name() == name) {
+            issues.push(Issue {
+                file: format!("{}.kdl", name),
+                line: None,
+                kind: IssueKind::Unreferenced,
+                message: "not referenced by any catalog entry".to_string(),
+            });
+        }
+    }
+
+    // Group by file, with catalog-level issues last
+    issues.sort_by_key(|i| (i.file == "catalog", i.file.clone()));
+
+    Ok(issues)
+}
+
+struct Checker<'a> {
+    file: &'a str,
+    issues: &'a mut Vec<Issue>,
+}
+
+impl Checker<'_> {
+    /// Checks `node` and its children. `cwd` is the effective working
+    /// directory inherited from enclosing layout/tab/pane nodes, if known.
+    fn walk(&mut self, node: &Node, cwd: Option<&Path>) {
+        let mut cwd = cwd.map(Path::to_path_buf);
+        if let Some(own) = layout::node_cwd(node) {
+            let resolved = resolve(cwd.as_deref(), &own);
+            let missing = resolved.is_absolute() && !resolved.exists();
+            if missing {
+                self.push(node, IssueKind::MissingPath, format!("cwd `{}` does not exist", own));
+            }
+            // Relative cwds without an absolute parent depend on where zellij was
+            // started; below a missing cwd they would only repeat the same problem
+            cwd = (resolved.is_absolute() && !missing).then_some(resolved);
+        }
+
+        if let Some(cmd) = layout::pane_command(node) {
+            self.check_command(node, &cmd.command, cwd.as_deref());
+        }
+
+        let values = node
+            .args
+            .iter()
+            .chain(node.props.iter().filter(|(k, _)| k != "command" && k != "cwd").map(|(_, v)| v));
+        if !matches!(node.name.as_str(), "cwd" | "command") {
+            for value in values {
+                if let Value::String(s) = value {
+                    self.check_path_value(node, s);
+                }
+            }
+        }
+
+        for child in &node.children {
+            self.walk(child, cwd.as_deref());
+        }
+    }
+
+    fn check_command(&mut self, node: &Node, command: &str, cwd: Option<&Path>) {
+        if command.contains('/') || command.starts_with('~') {
+            let path = resolve(cwd, command);
+            if path.is_absolute() && !path.exists() {
+                self.push(node, IssueKind::MissingCommand, format!("command `{}` does not exist", command));
+            }
+        } else if !on_path(command) {
+            self.push(node, IssueKind::MissingCommand, format!("command `{}` not found on PATH", command));
+        }
+    }
+
+    /// Flags absolute (or `~`-relative) paths in arguments, e.g. the
+    /// `nix develop /Users/...` args or `scrollback_editor`.
+    fn check_path_value(&mut self, node: &Node, value: &str) {
+        let candidate = value.strip_prefix("file:").unwrap_or(value);
+        if !(candidate.starts_with('/') || candidate.starts_with("~/")) {
+            return;
+        }
+        if !catalog::expand_home(candidate).exists() {
+            self.push(node, IssueKind::MissingPath, format!("path `{}` does not exist", candidate));
+        }
+    }
+
+    fn push(&mut self, node: &Node, kind: IssueKind, message: String) {
+        self.issues.push(Issue {
+            file: self.file.to_string(),
+            line: Some(node.line),
+            kind,
+            message,
+        });
+    }
+}
+
+fn resolve(base: Option<&Path>, path: &str) -> PathBuf {
+    let expanded = catalog::expand_home(path);
+    match base {
+        Some(base) if expanded.is_relative() => base.join(expanded),
+        _ => expanded,
+    }
+}
+
+fn on_path(command: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(command).is_file()))
+        .unwrap_or(false)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn print_human(issues: &[Issue]) {
+    if issues.is_empty() {
+        println!("No problems found.");
+        return;
+    }
+
+    let mut current_file = None;
+    for issue in issues {
+        if current_file != Some(&issue.file) {
+            if current_file.is_some() {
+                println!();
+            }
+            println!("{}", issue.file);
+            current_file = Some(&issue.file);
+        }
+        let line = issue.line.map(|l| format!("{:>4}: ", l)).unwrap_or_else(|| "      ".to_string());
+        println!("  {}{:<16} {}", line, issue.kind.label(), issue.message);
+    }
+
+    let files: BTreeSet<&str> = issues.iter().map(|i| i.file.as_str()).collect();
+    println!(
+        "\n{} problem{} in {} file{}",
+        issues.len(),
+        if issues.len() == 1 { "" } else { "s" },
+        files.len(),
+        if files.len() == 1 { "" } else { "s" }
+    );
+}
@@ -1,10 +1,42 @@
+mod actions;
+mod batch;
+mod catalog;
+mod cli;
+mod discover;
+mod env;
+mod generate;
+mod git;
+mod health;
+mod history;
+mod hooks;
+mod kdl;
+mod layout;
+mod lint;
+mod minimap;
+mod naming;
+mod pins;
+mod shell;
+mod tracking;
+mod usage;
+mod warm;
+mod wizard;
+mod worktree;
+mod zellij;
+
 use anyhow::{Context, Result};
+use catalog::{Catalog, Session};
 use chrono::Local;
-use clap::Parser;
+use clap::{CommandFactory, Parser, Subcommand};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{
+        self, Event, KeyCode, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
@@ -13,120 +45,807 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Frame, Terminal,
 };
+use std::collections::BTreeSet;
 use std::io;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+use zellij::RunningSession;
 
 #[derive(Parser, Debug)]
 #[command(name = "zellij-welcome")]
 #[command(about = "Beautiful Zellij session picker", long_about = None)]
 struct Args {
     /// Minimal mode (compact session picker)
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     minimal: bool,
+
+    /// Directory whose entry the picker starts on, and `open .` opens
+    /// (defaults to the current one)
+    #[arg(long, global = true)]
+    cwd: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
 }
 
-struct Session {
-    name: String,
-    description: String,
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Check layouts for syntax errors, missing paths/commands and catalog drift
+    LintLayouts {
+        /// Print issues as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render layouts of catalog entries that declare a template
+    GenerateLayouts {
+        /// Diff the generated layouts against the ones on disk instead of writing
+        #[arg(long)]
+        check: bool,
+        /// Only generate these catalog entries
+        names: Vec<String>,
+    },
+    /// List catalog entries and their running instances
+    List {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Attach to a running instance of an entry, or create one
+    Open {
+        /// Catalog entry name, or `.` for the one whose project contains
+        /// the current directory
+        name: String,
+        /// Create or resurrect the session detached instead of switching to it
+        #[arg(long)]
+        background: bool,
+    },
+    /// Kill a zellij session, or all running instances of a catalog entry
+    Kill {
+        /// Session or catalog entry name
+        name: String,
+    },
+    /// Delete sessions that don't belong to any catalog entry
+    Cleanup,
+    /// Start the catalog's `autostart` entries in the background
+    Warmup,
+    /// Build the nix devshells of flake-based entries ahead of time
+    Warm {
+        /// Only these catalog entries (default: every entry and discovered
+        /// project with a flake.nix)
+        names: Vec<String>,
+    },
+    /// Run the picker without the automatic cleanup
+    Pick {
+        /// Print the chosen entry to stdout instead of launching it
+        #[arg(long)]
+        print: bool,
+        /// Start in most-recently-used order on the previous session
+        #[arg(long)]
+        mru: bool,
+    },
+    /// Switch to the previously active session
+    Back,
+    /// Print aliases, a picker key binding (Alt-z) and completions for a shell
+    ShellInit {
+        shell: shell::Shell,
+    },
+    /// Summarize tracked time per project
+    Report {
+        /// Group by day or ISO week
+        #[arg(long, value_enum, default_value = "day")]
+        per: tracking::Period,
+        #[arg(long, value_enum, default_value = "table")]
+        format: tracking::Format,
+        /// Only include time from this date on (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+        /// Gaps without activity longer than this many minutes don't count
+        /// (defaults to `tracking.idle_minutes` from the catalog)
+        #[arg(long)]
+        idle: Option<u64>,
+    },
+    /// Record activity in the current session (used by `shell-init`)
+    #[command(hide = true)]
+    Track,
+    /// Print completion candidates (used by `shell-init`)
+    #[command(hide = true)]
+    Complete {
+        candidates: shell::Candidates,
+    },
 }
 
-impl Session {
-    fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
-        Self {
-            name: name.into(),
-            description: description.into(),
-        }
-    }
+/// Minimum terminal width at which the preview pane is shown
+const PREVIEW_MIN_WIDTH: u16 = 100;
+
+/// Weight of an entry's frecency relative to its fuzzy match score
+const FRECENCY_BOOST: f64 = 5.0;
+
+/// Fuzzy score taken off discovered projects, so catalog entries win
+/// similar matches
+const PROJECT_PENALTY: i64 = 20;
+
+/// Width of the list's git column
+const GIT_COLUMN_WIDTH: usize = 24;
+
+/// Width of the list's resource usage column
+const USAGE_COLUMN_WIDTH: usize = 18;
+
+/// How often the picker redraws while waiting for keys
+const TICK: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Order {
+    /// Most frequently and recently launched first
+    Frecency,
+    /// Most recently used first, like alt-tab
+    Recent,
+    /// As listed in the catalog
+    Catalog,
+    /// Heaviest running sessions first, by CPU, then memory
+    Usage,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    /// Typing filters the list
+    Search,
+    /// Letters are commands and catalog hotkeys
+    Normal,
+}
+
+/// Keys normal mode uses itself; catalog hotkeys can't override them
+const NORMAL_MODE_KEYS: [char; 5] = ['j', 'k', 'q', 'i', '/'];
+
+/// What the picker was closed with
+enum Picked {
+    Entry(Box<Session>),
+    /// A specific running session, e.g. the previous one
+    Instance(String),
+    /// A new session from the `new` form
+    New {
+        name: String,
+        layout: String,
+        cwd: Option<PathBuf>,
+    },
+    /// An entry with hooks, already launched from the picker
+    Launched,
+}
+
+/// What a background start reports to the picker
+enum StartProgress {
+    /// A line of hook output
+    Output(String),
+    Finished(Result<cli::Started, String>),
 }
 
 struct App {
-    sessions: Vec<Session>,
+    catalog: Catalog,
+    layouts: Vec<Result<layout::LayoutInfo, String>>,
+    running: Vec<RunningSession>,
     selected: usize,
     minimal: bool,
     search_query: String,
     filtered_indices: Vec<usize>,
+    show_preview: bool,
+    /// Tab of the highlighted layout shown in the preview's mini-map
+    preview_tab: usize,
+    /// Frecency score per catalog entry, from the launch history
+    frecency: Vec<f64>,
+    /// When each catalog entry was last used, from time tracking
+    last_used: Vec<Option<i64>>,
+    order: Order,
+    /// Names of pinned entries, listed first
+    pins: Vec<String>,
+    mode: Mode,
+    /// Result of the last action, shown in the footer
+    status: Option<String>,
+    dialog: Option<actions::Dialog>,
+    /// Catalog indices of the entries marked for a batch action
+    marked: BTreeSet<usize>,
+    batch: Option<batch::Batch>,
+    /// Entries being started in the background, by catalog index, with
+    /// what stops their hooks when the picker closes
+    starting: Vec<(usize, Receiver<StartProgress>, hooks::Cancel)>,
+    wizard: Option<wizard::Wizard>,
+    /// The running background scan for project entries
+    discovery: Option<Receiver<Vec<PathBuf>>>,
+    /// Git state of the entries' working directories
+    git: git::Statuses,
+    /// Whether the list shows the git column
+    show_git: bool,
+    /// Where the picker was invoked from
+    dir: PathBuf,
+    /// Catalog index of the entry whose project contains `dir`
+    here: Option<usize>,
+    /// The launch of an entry with hooks, showing their output
+    hooks: Option<hooks::Panel>,
+    /// Devshell builds started from the picker
+    warmer: warm::Warmer,
+    /// Health of the entries' services
+    health: health::Probes,
+    /// CPU and memory of the running sessions
+    usage: usage::Monitor,
+    /// Whether the list shows the resource usage column
+    show_usage: bool,
+    /// Picked entries are printed rather than launched (`pick --print`)
+    print: bool,
 }
 
 impl App {
-    fn new(minimal: bool) -> Self {
-        let sessions = vec![
-            Session::new("new", "Start a new session"),
-            Session::new("energyboard", "Energy management portal"),
-            Session::new("easyasset", "Asset tracking platform"),
-            Session::new("colony", "Multi-agent dev environments"),
-            Session::new("backoffice", "Admin backend systems"),
-            Session::new("gappel-solutions", "Company solutions"),
-            Session::new("decon", "Decon project"),
-            Session::new("screensaver", "Screensaver development"),
-            Session::new("lazychat", "Lazychat TUI for Claude sessions"),
-            Session::new("elixir", "Elixir projects"),
-            Session::new("msp", "Smartpower MSP platform"),
-            Session::new("smartflex", "SMARTFLEX foundation system"),
-            Session::new("lazylink", "TUI task board + agent coordination"),
-            Session::new("welcome", "Return to this screen"),
-        ];
-
-        let filtered_indices: Vec<usize> = (0..sessions.len()).collect();
-
-        Self {
-            sessions,
+    fn new(minimal: bool, mut catalog: Catalog, order: Order, dir: &Path) -> Self {
+        // Projects from the last scan show right away; a fresh scan follows
+        let projects = discover::entries(&catalog, &discover::load_cache());
+        catalog.sessions.extend(projects);
+        let discovery = (!catalog.discover.roots.is_empty()).then(|| {
+            let config = catalog.discover.clone();
+            let (tx, rx) = mpsc::channel();
+            std::thread::spawn(move || {
+                let _ = tx.send(discover::scan(&config));
+            });
+            rx
+        });
+
+        let mut app = Self {
+            catalog,
+            layouts: Vec::new(),
+            running: zellij::list_sessions(),
             selected: 0,
             minimal,
             search_query: String::new(),
-            filtered_indices,
+            filtered_indices: Vec::new(),
+            show_preview: true,
+            preview_tab: 0,
+            frecency: Vec::new(),
+            last_used: Vec::new(),
+            order,
+            pins: pins::load(),
+            mode: Mode::Search,
+            status: None,
+            dialog: None,
+            marked: BTreeSet::new(),
+            batch: None,
+            starting: Vec::new(),
+            wizard: None,
+            discovery,
+            git: git::Statuses::new(),
+            show_git: false,
+            dir: dir.to_path_buf(),
+            here: None,
+            hooks: None,
+            warmer: warm::Warmer::new(),
+            health: health::Probes::new(),
+            usage: usage::Monitor::start(),
+            show_usage: false,
+            print: false,
+        };
+        app.load_entry_state();
+        app.update_filtered_indices();
+        app.selected = app.most_likely();
+        app
+    }
+
+    /// Loads the layout, frecency and last use of every entry, finds the one
+    /// the picker was invoked in and starts reading the git state of their
+    /// working directories.
+    fn load_entry_state(&mut self) {
+        let sessions = &self.catalog.sessions;
+        self.layouts = sessions
+            .iter()
+            .map(|s| layout::load(&self.catalog.layout_path(s)))
+            .collect();
+
+        let scores = history::History::load().frecency();
+        self.frecency = sessions
+            .iter()
+            .map(|s| scores.get(&s.name).copied().unwrap_or(0.0))
+            .collect();
+
+        let current = tracking::current_session(&self.running);
+        let recent = tracking::recent_sessions();
+        self.last_used = sessions
+            .iter()
+            .map(|s| {
+                // The current session counts as used right now
+                if current.as_deref().is_some_and(|c| s.owns(c)) {
+                    return Some(i64::MAX);
+                }
+                recent.iter().find(|(name, _)| s.owns(name)).map(|&(_, t)| t)
+            })
+            .collect();
+
+        let roots: Vec<(usize, PathBuf)> = (0..sessions.len())
+            .filter_map(|i| Some((i, self.working_dir(i)?)))
+            .collect();
+        self.here = catalog::deepest_root(&self.dir, roots.iter().cloned());
+        if self.catalog.nix.auto_warm {
+            // Rebuild devshells whose flake.lock changed, unless that failed before
+            let outdated: Vec<warm::Target> = roots
+                .iter()
+                .filter_map(|(i, dir)| warm::Target::new(&sessions[*i], dir.clone()))
+                .filter(|t| t.is_outdated() && self.warmer.state(&t.dir, &t.installable).is_none())
+                .collect();
+            self.warmer.request(outdated);
         }
+        self.git.request(roots.into_iter().map(|(_, dir)| dir));
+    }
+
+    /// The entry's cwd, or its layout's.
+    fn working_dir(&self, index: usize) -> Option<PathBuf> {
+        let session = &self.catalog.sessions[index];
+        let layout_cwd = || self.layouts[index].as_ref().ok().and_then(|l| l.cwd.clone());
+        let cwd = session.cwd.clone().or_else(layout_cwd)?;
+        Some(catalog::expand_home(cwd.trim_end_matches('/')))
+    }
+
+    /// Replaces the project entries once the background scan is done.
+    fn poll_discovery(&mut self) {
+        let Some(rx) = &self.discovery else { return };
+        let repos = match rx.try_recv() {
+            Ok(repos) => repos,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.discovery = None;
+                return;
+            }
+        };
+        self.discovery = None;
+        if let Err(e) = discover::save_cache(&repos) {
+            self.status = Some(format!("{:#}", e));
+        }
+
+        let first_project = self.catalog.sessions.iter().position(|s| s.project).unwrap_or(self.catalog.sessions.len());
+        let mut catalog_only = self.catalog.clone();
+        catalog_only.sessions.truncate(first_project);
+        let projects = discover::entries(&catalog_only, &repos);
+        let unchanged = self.catalog.sessions[first_project..]
+            .iter()
+            .map(|s| (&s.name, &s.cwd))
+            .eq(projects.iter().map(|s| (&s.name, &s.cwd)));
+        if unchanged {
+            return;
+        }
+
+        let selected = self.get_selected_session().map(|s| s.name.clone());
+        self.catalog.sessions.truncate(first_project);
+        self.catalog.sessions.extend(projects);
+        self.marked.retain(|&i| i < first_project);
+        self.starting.retain(|&(i, ..)| i < first_project);
+        self.load_entry_state();
+        self.update_filtered_indices();
+        self.selected = selected
+            .and_then(|name| {
+                self.filtered_indices
+                    .iter()
+                    .position(|&i| self.catalog.sessions[i].name == name)
+            })
+            .unwrap_or_else(|| self.most_likely());
     }
 
     fn next(&mut self) {
-        if !self.filtered_indices.is_empty() {
-            self.selected = (self.selected + 1) % self.filtered_indices.len();
+        self.preview_tab = 0;
+        if self.row_count() > 0 {
+            self.selected = (self.selected + 1) % self.row_count();
         }
     }
 
     fn previous(&mut self) {
-        if !self.filtered_indices.is_empty() {
+        self.preview_tab = 0;
+        if self.row_count() > 0 {
             if self.selected > 0 {
                 self.selected -= 1;
             } else {
-                self.selected = self.filtered_indices.len() - 1;
+                self.selected = self.row_count() - 1;
             }
         }
     }
 
-    fn get_selected_session(&self) -> &str {
-        if !self.filtered_indices.is_empty() {
-            let actual_idx = self.filtered_indices[self.selected];
-            &self.sessions[actual_idx].name
-        } else {
-            ""
+    /// Visible list rows: the matching entries plus the create row.
+    fn row_count(&self) -> usize {
+        self.filtered_indices.len() + usize::from(self.create_name().is_some())
+    }
+
+    /// The session name the create row offers, if it is shown: the search
+    /// query, when nothing matches it or `create.always` is set.
+    fn create_name(&self) -> Option<String> {
+        let query = self.search_query.trim();
+        if query.is_empty() || !(self.filtered_indices.is_empty() || self.catalog.create.always) {
+            return None;
         }
+        Some(query.chars().map(|c| if c.is_whitespace() || c == '/' { '-' } else { c }).collect())
+    }
+
+    fn on_create_row(&self) -> bool {
+        self.selected == self.filtered_indices.len() && self.create_name().is_some()
+    }
+
+    /// Switches to the session named by the query, creating it with the
+    /// `create` layout and a matching project directory as cwd.
+    fn create_from_query(&self) -> Option<Picked> {
+        let name = self.create_name()?;
+        if self.running.iter().any(|r| r.name == name) {
+            return Some(Picked::Instance(name));
+        }
+        Some(Picked::New {
+            cwd: self.catalog.create.resolve_cwd(&name),
+            layout: self.catalog.create.layout.clone(),
+            name,
+        })
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        self.filtered_indices.get(self.selected).copied()
+    }
+
+    fn get_selected_session(&self) -> Option<&Session> {
+        self.selected_index().map(|i| &self.catalog.sessions[i])
+    }
+
+    fn cycle_preview_tab(&mut self, forward: bool) {
+        let tab_count = self
+            .selected_index()
+            .and_then(|i| self.layouts[i].as_ref().ok())
+            .map_or(0, |l| l.tabs.len());
+        if tab_count == 0 {
+            return;
+        }
+        self.preview_tab = if forward {
+            (self.preview_tab + 1) % tab_count
+        } else {
+            (self.preview_tab + tab_count - 1) % tab_count
+        };
+    }
+
+    /// Running (or exited) zellij sessions created from the given entry
+    fn instances_of<'a>(&'a self, session: &'a Session) -> impl Iterator<Item = &'a RunningSession> + 'a {
+        self.running.iter().filter(move |r| session.owns(&r.name))
     }
 
     fn update_search(&mut self, query: String) {
         self.search_query = query;
         self.update_filtered_indices();
-        self.selected = 0;
+        self.selected = self.most_likely();
+        self.preview_tab = 0;
+    }
+
+    /// Cycles frecency, most-recently-used and catalog order, keeping the
+    /// selection.
+    fn toggle_order(&mut self) {
+        let current = self.selected_index();
+        self.order = match self.order {
+            Order::Frecency => Order::Recent,
+            Order::Recent => Order::Catalog,
+            Order::Catalog => Order::Usage,
+            Order::Usage => Order::Frecency,
+        };
+        self.update_filtered_indices();
+        self.selected = current
+            .and_then(|i| self.filtered_indices.iter().position(|&j| j == i))
+            .unwrap_or(0);
+    }
+
+    fn is_pinned(&self, index: usize) -> bool {
+        self.pins.contains(&self.catalog.sessions[index].name)
+    }
+
+    /// Pins or unpins the highlighted entry, keeping it highlighted.
+    fn toggle_pin(&mut self) {
+        let Some(index) = self.selected_index() else { return };
+        let name = self.catalog.sessions[index].name.clone();
+        let pinned = if let Some(pos) = self.pins.iter().position(|p| *p == name) {
+            self.pins.remove(pos);
+            false
+        } else {
+            self.pins.push(name.clone());
+            true
+        };
+        self.status = Some(match pins::save(&self.pins) {
+            Ok(()) if pinned => format!("Pinned {}", name),
+            Ok(()) => format!("Unpinned {}", name),
+            Err(e) => format!("{:#}", e),
+        });
+
+        self.update_filtered_indices();
+        self.selected = self.filtered_indices.iter().position(|&i| i == index).unwrap_or(0);
+    }
+
+    /// Marks the highlighted entry, or unmarks it.
+    fn toggle_mark(&mut self) {
+        let Some(index) = self.selected_index() else { return };
+        if !self.marked.remove(&index) {
+            self.marked.insert(index);
+        }
+    }
+
+    /// Starts the highlighted entry in the background; its row shows the
+    /// progress.
+    fn start_in_background(&mut self) {
+        let Some(index) = self.selected_index() else { return };
+        if self.starting.iter().any(|&(i, ..)| i == index) {
+            return;
+        }
+        let catalog = self.catalog.clone();
+        let (tx, rx) = mpsc::channel();
+        let cancel = hooks::Cancel::new();
+        let cancelled = cancel.clone();
+        std::thread::spawn(move || {
+            let session = &catalog.sessions[index];
+            let report = |event| {
+                if let hooks::Event::Stdout(line) | hooks::Event::Stderr(line) = event {
+                    let _ = tx.send(StartProgress::Output(line));
+                }
+            };
+            let started = cli::start_background(&catalog, session, &report, &cancelled);
+            let _ = tx.send(StartProgress::Finished(started.map_err(|e| format!("{:#}", e))));
+        });
+        self.starting.push((index, rx, cancel));
+    }
+
+    /// Shows hook output of background starts in the status line and picks
+    /// up the ones that have finished.
+    fn poll_starting(&mut self) {
+        let mut finished = false;
+        self.starting.retain(|(index, rx, _)| {
+            let name = &self.catalog.sessions[*index].name;
+            loop {
+                match rx.try_recv() {
+                    Err(TryRecvError::Empty) => return true,
+                    Ok(StartProgress::Output(line)) => {
+                        if !line.trim().is_empty() {
+                            self.status = Some(format!("{}: {}", name, line));
+                        }
+                    }
+                    Ok(StartProgress::Finished(result)) => {
+                        self.status = Some(match result {
+                            Ok(started) => format!("{}: {}", name, started),
+                            Err(e) => format!("{}: {}", name, e),
+                        });
+                        finished = true;
+                        return false;
+                    }
+                    Err(TryRecvError::Disconnected) => return false,
+                }
+            }
+        });
+        if finished {
+            self.running = zellij::list_sessions();
+        }
+    }
+
+    /// Stops the hooks of background starts still going, whose threads end
+    /// with the picker.
+    fn cancel_starting(&mut self) {
+        for (_, _, cancel) in self.starting.drain(..) {
+            cancel.cancel();
+        }
+    }
+
+    /// Queues devshell builds for the entries at `indices` that have a
+    /// flake; returns how many were queued.
+    fn warm(&mut self, indices: impl IntoIterator<Item = usize>) -> usize {
+        let targets: Vec<warm::Target> = indices
+            .into_iter()
+            .filter_map(|i| warm::Target::new(&self.catalog.sessions[i], self.working_dir(i)?))
+            .collect();
+        self.warmer.request(targets)
+    }
+
+    fn warm_selected(&mut self) {
+        let Some(index) = self.selected_index() else { return };
+        let name = self.catalog.sessions[index].name.clone();
+        let has_flake = self.working_dir(index).is_some_and(|dir| dir.join("flake.nix").exists());
+        self.status = Some(if !has_flake {
+            format!("{} has no flake.nix", name)
+        } else if self.warm([index]) == 0 {
+            format!("{} is already warming", name)
+        } else {
+            format!("Warming {}", name)
+        });
+    }
+
+    /// Takes in probe results, and probes every service again once the
+    /// last round is old enough.
+    fn poll_health(&mut self) {
+        self.health.poll();
+        if self.health.is_due() {
+            let services = self.catalog.sessions.iter().flat_map(|s| s.services.iter().cloned());
+            self.health.request(services);
+        }
+    }
+
+    /// Takes in the latest usage sample, re-sorting in usage order with the
+    /// highlighted entry kept.
+    fn poll_usage(&mut self) {
+        if !self.usage.poll() || self.order != Order::Usage || !self.search_query.is_empty() {
+            return;
+        }
+        let current = self.selected_index();
+        self.update_filtered_indices();
+        self.selected = current
+            .and_then(|i| self.filtered_indices.iter().position(|&j| j == i))
+            .unwrap_or(0);
+    }
+
+    /// The resource usage of the entry's running instances together.
+    fn entry_usage(&self, index: usize) -> Option<usage::Usage> {
+        let session = &self.catalog.sessions[index];
+        let instances = self.instances_of(session).filter(|r| !r.exited);
+        usage::Usage::total(instances.filter_map(|r| self.usage.get(&r.name)))
+    }
+
+    /// Shows finished devshell builds in the status line.
+    fn poll_warming(&mut self) {
+        if let Some(message) = self.warmer.poll().pop() {
+            self.status = Some(message);
+        }
+    }
+
+    /// Picks catalog entry `index`, except that `new` opens the form and
+    /// entries with hooks to run are launched from the picker.
+    fn choose(&mut self, index: usize) -> Option<Picked> {
+        let session = &self.catalog.sessions[index];
+        if session.name == "new" {
+            self.wizard = Some(wizard::Wizard::new(self));
+            return None;
+        }
+        let has_hooks = !session.pre_launch.is_empty() || !session.post_launch.is_empty();
+        // Hooks only run for new sessions, not when attaching
+        let attaching = self.instances_of(session).any(|r| !r.exited);
+        if has_hooks && !attaching && !self.print {
+            self.launch_with_hooks(index);
+            return None;
+        }
+        Some(Picked::Entry(Box::new(session.clone())))
+    }
+
+    /// Launches entry `index` on a worker thread, streaming the output of
+    /// its hooks into the panel.
+    fn launch_with_hooks(&mut self, index: usize) {
+        let catalog = self.catalog.clone();
+        let (tx, rx) = mpsc::channel();
+        let cancel = hooks::Cancel::new();
+        let cancelled = cancel.clone();
+        std::thread::spawn(move || {
+            let session = &catalog.sessions[index];
+            let report = |event| {
+                let _ = tx.send(hooks::Progress::Event(event));
+            };
+            let result = launch_session_with(&catalog, session, &report, &cancelled).map_err(|e| format!("{:#}", e));
+            let _ = tx.send(hooks::Progress::Finished(result));
+        });
+        self.hooks = Some(hooks::Panel::new(self.catalog.sessions[index].name.clone(), rx, cancel));
+    }
+
+    /// Whether digits open the Nth visible entry rather than go into the
+    /// search, which only happens in normal mode so that a search can start
+    /// with a digit.
+    fn quick_select(&self) -> bool {
+        self.mode == Mode::Normal
+    }
+
+    /// Catalog index of the entry with the given normal-mode hotkey.
+    fn hotkey_entry(&self, key: char) -> Option<usize> {
+        if NORMAL_MODE_KEYS.contains(&key) {
+            return None;
+        }
+        self.catalog.sessions.iter().position(|s| s.key == Some(key))
+    }
+
+    /// The launch key pressed again: switches to most-recently-used order,
+    /// then steps one session further back per press.
+    fn cycle_back(&mut self) {
+        if self.order == Order::Recent && self.search_query.is_empty() {
+            self.next();
+        } else {
+            self.order = Order::Recent;
+            self.search_query.clear();
+            self.update_filtered_indices();
+            self.selected = self.most_likely();
+            self.preview_tab = 0;
+        }
+    }
+
+    fn order_label(&self) -> &'static str {
+        match self.order {
+            Order::Frecency => "frecency",
+            Order::Recent => "recent",
+            Order::Catalog => "catalog",
+            Order::Usage => "usage",
+        }
+    }
+
+    /// Position of the entry the user most likely wants: the best match while
+    /// searching, the previous session in most-recently-used order, otherwise
+    /// the one with the highest frecency.
+    fn most_likely(&self) -> usize {
+        if !self.search_query.is_empty() {
+            return 0;
+        }
+        if self.order == Order::Recent {
+            // The first entry is the one we are in
+            let first_is_current = self
+                .filtered_indices
+                .first()
+                .is_some_and(|&i| self.last_used[i] == Some(i64::MAX));
+            return usize::from(first_is_current && self.filtered_indices.len() > 1);
+        }
+        // The project we're in, unless stepping through recent sessions
+        let here = self.here.and_then(|h| self.filtered_indices.iter().position(|&i| i == h));
+        if let Some(position) = here {
+            return position;
+        }
+        self.filtered_indices
+            .iter()
+            .enumerate()
+            .filter(|(_, &i)| self.frecency[i] > 0.0)
+            .max_by(|(pa, &a), (pb, &b)| {
+                // Earliest position wins ties
+                self.frecency[a].total_cmp(&self.frecency[b]).then(pb.cmp(pa))
+            })
+            .map_or(0, |(pos, _)| pos)
     }
 
     fn update_filtered_indices(&mut self) {
         if self.search_query.is_empty() {
-            self.filtered_indices = (0..self.sessions.len()).collect();
+            self.filtered_indices = (0..self.catalog.sessions.len()).collect();
+            // Stable sorts, so entries without history keep their catalog order
+            match self.order {
+                Order::Frecency => {
+                    let frecency = &self.frecency;
+                    self.filtered_indices.sort_by(|&a, &b| frecency[b].total_cmp(&frecency[a]));
+                }
+                Order::Recent => {
+                    let last_used = &self.last_used;
+                    self.filtered_indices.sort_by_key(|&i| std::cmp::Reverse(last_used[i]));
+                }
+                Order::Catalog => {}
+                Order::Usage => {
+                    let usage: Vec<Option<usage::Usage>> =
+                        (0..self.catalog.sessions.len()).map(|i| self.entry_usage(i)).collect();
+                    self.filtered_indices.sort_by(|&a, &b| match (&usage[a], &usage[b]) {
+                        (Some(a), Some(b)) => b.cpu.total_cmp(&a.cpu).then(b.rss.cmp(&a.rss)),
+                        (a, b) => b.is_some().cmp(&a.is_some()),
+                    });
+                }
+            }
+            // Discovered projects rank below the catalog's own entries,
+            // except when stepping back through recent sessions or looking
+            // for the heaviest ones
+            if !matches!(self.order, Order::Recent | Order::Usage) {
+                let sessions = &self.catalog.sessions;
+                self.filtered_indices.sort_by_key(|&i| sessions[i].project);
+            }
+
+            // Favorites go first, except in most-recently-used order where
+            // they would break stepping back through recent sessions, and in
+            // usage order
+            if !matches!(self.order, Order::Recent | Order::Usage) {
+                let pinned: Vec<usize> = self
+                    .pins
+                    .iter()
+                    .filter_map(|name| self.catalog.sessions.iter().position(|s| s.name == *name))
+                    .collect();
+                self.filtered_indices.retain(|i| !pinned.contains(i));
+                self.filtered_indices.splice(0..0, pinned);
+            }
         } else {
             let matcher = SkimMatcherV2::default();
-            let mut matches: Vec<(usize, i64)> = self.sessions
+            let mut matches: Vec<(usize, i64)> = self.catalog.sessions
                 .iter()
                 .enumerate()
                 .filter_map(|(i, session)| {
+                    let penalty = if session.project { PROJECT_PENALTY } else { 0 };
                     matcher.fuzzy_match(&session.name, &self.search_query)
-                        .map(|score| (i, score))
+                        .map(|score| (i, score + (self.frecency[i].ln_1p() * FRECENCY_BOOST) as i64 - penalty))
                 })
                 .collect();
 
             // Sort by score (highest first)
-            matches.sort_by(|a, b| b.1.cmp(&a.1));
+            matches.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
 
             self.filtered_indices = matches.into_iter().map(|(i, _)| i).collect();
         }
@@ -135,44 +854,267 @@ impl App {
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    let mut catalog = Catalog::load()?;
+    // Worktrees take git calls, and `track` and `complete` run on every
+    // prompt and tab, so only the commands that list or launch entries look
+    // them up
+    let uses_worktrees = matches!(
+        args.command,
+        None | Some(
+            Commands::List { .. }
+                | Commands::Open { .. }
+                | Commands::Kill { .. }
+                | Commands::Cleanup
+                | Commands::Warmup
+                | Commands::Warm { .. }
+                | Commands::Pick { .. }
+        )
+    );
+    if uses_worktrees {
+        worktree::expand(&mut catalog);
+    }
+    let invocation_dir = match args.cwd {
+        Some(dir) => dir,
+        None => std::env::current_dir().context("Failed to read the current directory")?,
+    };
+
+    match args.command {
+        Some(Commands::LintLayouts { json }) => {
+            let issue_count = lint::run(&catalog, json)?;
+            std::process::exit(if issue_count > 0 { 1 } else { 0 });
+        }
+        Some(Commands::GenerateLayouts { check, names }) => {
+            let up_to_date = generate::run(&catalog, check, &names)?;
+            std::process::exit(if up_to_date { 0 } else { 1 });
+        }
+        Some(Commands::List { json }) => cli::list(&catalog, json),
+        Some(Commands::Open { name, background }) => {
+            let session = if name == "." {
+                cli::find_by_dir(&mut catalog, &invocation_dir)?
+            } else {
+                cli::find(&catalog, &name)?.clone()
+            };
+            if background {
+                println!("{}", cli::start_background(&catalog, &session, &hooks::print, &hooks::Cancel::none())?);
+                Ok(())
+            } else {
+                launch_session(&catalog, &session)
+            }
+        }
+        Some(Commands::Warmup) => cli::warmup(&catalog),
+        Some(Commands::Warm { names }) => warm::run(&catalog, &names),
+        Some(Commands::Kill { name }) => cli::kill(&catalog, &name),
+        Some(Commands::Cleanup) => {
+            for name in cleanup_old_sessions(&catalog) {
+                println!("deleted {}", name);
+            }
+            Ok(())
+        }
+        Some(Commands::ShellInit { shell }) => {
+            let subcommands: Vec<String> = Args::command()
+                .get_subcommands()
+                .filter(|c| !c.is_hide_set())
+                .map(|c| c.get_name().to_string())
+                .collect();
+            print!("{}", shell::init(&catalog, shell, &subcommands));
+            Ok(())
+        }
+        Some(Commands::Report { per, format, since, idle }) => {
+            tracking::report(&catalog, per, format, since, idle)
+        }
+        Some(Commands::Track) => tracking::heartbeat(&catalog),
+        Some(Commands::Complete { candidates }) => {
+            shell::complete(&catalog, candidates);
+            Ok(())
+        }
+        Some(Commands::Pick { print, mru }) => {
+            let order = if mru { Order::Recent } else { Order::Frecency };
+            // When printing, stdout is usually captured, so draw on stderr
+            let picked = if print {
+                pick(args.minimal, catalog.clone(), order, &invocation_dir, true, io::stderr())?
+            } else {
+                pick(args.minimal, catalog.clone(), order, &invocation_dir, false, io::stdout())?
+            };
+            match picked {
+                Some(Picked::Entry(session)) if print => println!("{}", session.name),
+                Some(Picked::Instance(name) | Picked::New { name, .. }) if print => println!("{}", name),
+                Some(picked) => open_picked(&catalog, picked)?,
+                None => {}
+            }
+            Ok(())
+        }
+        Some(Commands::Back) => {
+            let previous = tracking::previous_session(&zellij::list_sessions())
+                .context("No previous session to go back to")?;
+            switch_session(&catalog, &previous, &format!("-s {}", previous))
+        }
+        None => {
+            // Clean up old sessions first
+            cleanup_old_sessions(&catalog);
+
+            if let Some(picked) = pick(args.minimal, catalog.clone(), Order::Frecency, &invocation_dir, false, io::stdout())? {
+                open_picked(&catalog, picked)?;
+            }
+            Ok(())
+        }
+    }
+}
 
-    // Clean up old sessions first
-    cleanup_old_sessions()?;
+fn open_picked(catalog: &Catalog, picked: Picked) -> Result<()> {
+    match picked {
+        Picked::Entry(session) if session.project => {
+            // Launches of discovered projects are recorded like the catalog's
+            let mut catalog = catalog.clone();
+            catalog.sessions.push((*session).clone());
+            launch_session(&catalog, &session)
+        }
+        Picked::Entry(session) => launch_session(catalog, &session),
+        Picked::Instance(name) => switch_session(catalog, &name, &format!("-s {}", name)),
+        Picked::New { name, layout, cwd } => create_and_switch(catalog, &name, &layout, cwd.as_deref()),
+        Picked::Launched => Ok(()),
+    }
+}
 
+/// Runs the interactive picker on `out` and returns the choice.
+fn pick<W: io::Write>(minimal: bool, catalog: Catalog, order: Order, dir: &Path, print: bool, mut out: W) -> Result<Option<Picked>> {
     // Setup terminal
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
+    execute!(out, EnterAlternateScreen)?;
+    // Lets the terminal report Shift-Enter, where it speaks the kitty protocol
+    let enhanced = supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced {
+        execute!(out, PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES))?;
+    }
+    let backend = CrosstermBackend::new(out);
     let mut terminal = Terminal::new(backend)?;
 
     // Create app and run
-    let mut app = App::new(args.minimal);
+    let mut app = App::new(minimal, catalog, order, dir);
+    app.print = print;
     let result = run_app(&mut terminal, &mut app);
+    app.cancel_starting();
 
     // Restore terminal
     disable_raw_mode()?;
+    if enhanced {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
-    // Handle the result
-    if let Ok(Some(session)) = result {
-        launch_session(&session)?;
-    }
-
-    Ok(())
+    result
 }
 
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
-) -> Result<Option<String>> {
+) -> Result<Option<Picked>> {
     loop {
+        batch::poll(app);
+        app.poll_starting();
+        app.poll_discovery();
+        app.git.poll();
+        app.poll_warming();
+        app.poll_health();
+        app.poll_usage();
+        if hooks::poll(app) {
+            return Ok(Some(Picked::Launched));
+        }
         terminal.draw(|f| ui(f, app))?;
 
+        // Wake up regularly so background work can report progress
+        if !event::poll(TICK)? {
+            continue;
+        }
         if let Event::Key(key) = event::read()? {
             if key.kind == KeyEventKind::Press {
+                if app.hooks.is_some() {
+                    hooks::handle_key(app, key);
+                    continue;
+                }
+                if app.batch.is_some() {
+                    batch::handle_key(app, key);
+                    continue;
+                }
+                if app.dialog.is_some() {
+                    actions::handle_key(app, key);
+                    continue;
+                }
+                if app.wizard.is_some() {
+                    if let Some(picked) = wizard::handle_key(app, key) {
+                        return Ok(Some(picked));
+                    }
+                    continue;
+                }
+                app.status = None;
                 match key.code {
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.show_preview = !app.show_preview;
+                    }
+                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.show_git = !app.show_git;
+                    }
+                    KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.warm_selected();
+                    }
+                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.show_usage = !app.show_usage;
+                    }
+                    KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        actions::start_kill_process(app);
+                    }
+                    KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.toggle_order();
+                    }
+                    KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        app.toggle_pin();
+                    }
+                    KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        actions::start(app, actions::Action::Kill);
+                    }
+                    KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        actions::start(app, actions::Action::Delete);
+                    }
+                    KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        actions::start(app, actions::Action::Rename);
+                    }
+                    KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        actions::start(app, actions::Action::Detach);
+                    }
+                    KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        if app.marked.is_empty() {
+                            app.status = Some("Mark entries with Tab first".to_string());
+                        } else {
+                            app.batch = Some(batch::Batch::Menu);
+                        }
+                    }
+                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        if let Some(previous) = tracking::previous_session(&app.running) {
+                            return Ok(Some(Picked::Instance(previous)));
+                        }
+                    }
+                    // The shell's launch key (see `shell-init`)
+                    KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::ALT) => {
+                        app.cycle_back();
+                    }
+                    // Quick-select the Nth visible entry
+                    KeyCode::Char(c @ '1'..='9') if app.quick_select() => {
+                        let position = c as usize - '1' as usize;
+                        if let Some(&index) = app.filtered_indices.get(position) {
+                            if let Some(picked) = app.choose(index) {
+                                return Ok(Some(picked));
+                            }
+                        }
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc if app.mode == Mode::Normal => return Ok(None),
+                    KeyCode::Char('/') | KeyCode::Char('i') if app.mode == Mode::Normal => {
+                        app.mode = Mode::Search;
+                    }
+                    KeyCode::Char(c) if app.mode == Mode::Normal && app.hotkey_entry(c).is_some() => {
+                        if let Some(picked) = app.hotkey_entry(c).and_then(|i| app.choose(i)) {
+                            return Ok(Some(picked));
+                        }
+                    }
                     KeyCode::Char('q') => {
                         if app.search_query.is_empty() {
                             return Ok(None);
@@ -185,33 +1127,41 @@ fn run_app<B: ratatui::backend::Backend>(
                     }
                     KeyCode::Esc => {
                         if app.search_query.is_empty() {
-                            return Ok(None);
+                            // Like vim, Esc leaves insert mode first
+                            app.mode = Mode::Normal;
                         } else {
                             // Clear search
                             app.update_search(String::new());
                         }
                     }
+                    // Space goes into the search while searching
+                    KeyCode::Tab | KeyCode::Char(' ') if key.code == KeyCode::Tab || app.mode == Mode::Normal => {
+                        app.toggle_mark();
+                        app.next();
+                    }
+                    KeyCode::Left => app.cycle_preview_tab(false),
+                    KeyCode::Right => app.cycle_preview_tab(true),
                     KeyCode::Char('j') | KeyCode::Down => app.next(),
                     KeyCode::Char('k') | KeyCode::Up => app.previous(),
+                    // Shift-Enter needs the kitty keyboard protocol; Alt-Enter works anywhere
+                    KeyCode::Enter if key.modifiers.intersects(KeyModifiers::SHIFT | KeyModifiers::ALT) => {
+                        app.start_in_background();
+                    }
+                    KeyCode::Enter if app.on_create_row() => return Ok(app.create_from_query()),
                     KeyCode::Enter => {
-                        let session = app.get_selected_session().to_string();
-                        if !session.is_empty() {
-                            return Ok(Some(session));
+                        if let Some(picked) = app.selected_index().and_then(|i| app.choose(i)) {
+                            return Ok(Some(picked));
                         }
                     }
-                    KeyCode::Backspace => {
-                        if !app.search_query.is_empty() {
-                            let mut new_query = app.search_query.clone();
-                            new_query.pop();
-                            app.update_search(new_query);
-                        }
+                    KeyCode::Backspace if app.mode == Mode::Search && !app.search_query.is_empty() => {
+                        let mut new_query = app.search_query.clone();
+                        new_query.pop();
+                        app.update_search(new_query);
                     }
-                    KeyCode::Char(c) => {
-                        if c != 'j' && c != 'k' {
-                            let mut new_query = app.search_query.clone();
-                            new_query.push(c);
-                            app.update_search(new_query);
-                        }
+                    KeyCode::Char(c) if app.mode == Mode::Search && c != 'j' && c != 'k' => {
+                        let mut new_query = app.search_query.clone();
+                        new_query.push(c);
+                        app.update_search(new_query);
                     }
                     _ => {}
                 }
@@ -228,6 +1178,19 @@ fn ui(f: &mut Frame, app: &App) {
     } else {
         render_full_ui(f, app, size);
     }
+
+    if let Some(dialog) = &app.dialog {
+        actions::render(f, dialog, size);
+    }
+    if let Some(batch) = &app.batch {
+        batch::render(f, app, batch, size);
+    }
+    if let Some(wizard) = &app.wizard {
+        wizard::render(f, wizard, size);
+    }
+    if let Some(panel) = &app.hooks {
+        hooks::render(f, panel, size);
+    }
 }
 
 fn render_minimal_ui(f: &mut Frame, app: &App, area: Rect) {
@@ -244,7 +1207,7 @@ fn render_minimal_ui(f: &mut Frame, app: &App, area: Rect) {
     let search_text = if app.search_query.is_empty() {
         Line::from(vec![
             Span::styled("> ", Style::default().fg(Color::Blue)),
-            Span::styled("Type to search...", Style::default().fg(Color::DarkGray)),
+            Span::styled(search_placeholder(app), Style::default().fg(Color::DarkGray)),
         ])
     } else {
         Line::from(vec![
@@ -265,12 +1228,12 @@ fn render_minimal_ui(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(search_widget, chunks[0]);
 
     // Session list (filtered)
-    let items: Vec<ListItem> = app
+    let mut items: Vec<ListItem> = app
         .filtered_indices
         .iter()
         .enumerate()
         .map(|(display_idx, &actual_idx)| {
-            let s = &app.sessions[actual_idx];
+            let s = &app.catalog.sessions[actual_idx];
             let style = if display_idx == app.selected {
                 Style::default()
                     .fg(Color::Black)
@@ -280,24 +1243,41 @@ fn render_minimal_ui(f: &mut Frame, app: &App, area: Rect) {
                 Style::default().fg(Color::White)
             };
 
-            let content = format!("  {}  {}", s.name, s.description);
-            ListItem::new(content).style(style)
+            let (number, pin, suffix) = row_marks(app, display_idx, actual_idx);
+            let git = git_summary(app, actual_idx).map(|g| format!("  {}", g)).unwrap_or_default();
+            let usage = usage_summary(app, actual_idx).map(|u| format!("  {}", u)).unwrap_or_default();
+            let content = format!("  {}{}{}  {}{}{}{}", number, pin, s.name, s.description, git, usage, suffix);
+            let mut spans = vec![Span::raw(content)];
+            spans.extend(service_badges(app, actual_idx));
+            ListItem::new(Line::from(spans)).style(style)
         })
         .collect();
 
+    items.extend(create_row(app));
     let list = List::new(items).block(
         Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::DarkGray))
-            .title(format!(" Zellij Sessions ({}/{}) ", app.filtered_indices.len(), app.sessions.len()))
+            .title(format!(
+                " Zellij Sessions ({}/{}) · {}{} ",
+                app.filtered_indices.len(),
+                app.catalog.sessions.len(),
+                app.order_label(),
+                marked_label(app)
+            ))
             .title_style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
     );
 
-    f.render_widget(list, chunks[1]);
+    let (list_area, preview_area) = split_preview(app, chunks[1]);
+    f.render_widget(list, list_area);
+    if let Some(preview_area) = preview_area {
+        render_preview(f, app, preview_area);
+    }
 
     // Footer
-    let footer = Paragraph::new("j/k: navigate • Enter: select • Esc: clear search • q: quit")
-        .style(Style::default().fg(Color::DarkGray))
+    let help = "j/k: move • ⏎: open • ⇧⏎: background • 1-9: open Nth (normal mode) • ^o: back • ^f: pin • tab: mark • ^b: batch • ^p: preview • ^g: git • ^w: warm • ^u: usage • ^s: order • q: quit";
+    let footer = Paragraph::new(app.status.as_deref().unwrap_or(help))
+        .style(Style::default().fg(if app.status.is_some() { Color::Yellow } else { Color::DarkGray }))
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::TOP));
 
@@ -308,7 +1288,7 @@ fn render_full_ui(f: &mut Frame, app: &App, area: Rect) {
     let height = area.height;
 
     // Calculate dynamic heights
-    let session_count = app.filtered_indices.len() as u16;
+    let session_count = app.row_count() as u16;
     let session_list_height = session_count + 2; // +2 for borders
 
     // Responsive layout based on terminal height
@@ -339,7 +1319,7 @@ fn render_full_ui_tall(f: &mut Frame, app: &App, area: Rect, session_list_height
         .split(area);
 
     // ASCII Art Header - official Zellij logo
-    let header = vec![
+    let header = [
         "███████╗███████╗██╗     ██╗     ██╗     ██╗",
         "╚══███╔╝██╔════╝██║     ██║     ██║     ██║",
         "  ███╔╝ █████╗  ██║     ██║     ██║     ██║",
@@ -380,7 +1360,7 @@ fn render_full_ui_tall(f: &mut Frame, app: &App, area: Rect, session_list_height
     // Search input with box
     let search_text = if app.search_query.is_empty() {
         Line::from(vec![
-            Span::styled(" Type to search...", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!(" {}", search_placeholder(app)), Style::default().fg(Color::DarkGray)),
         ])
     } else {
         Line::from(vec![
@@ -402,12 +1382,12 @@ fn render_full_ui_tall(f: &mut Frame, app: &App, area: Rect, session_list_height
     f.render_widget(search_widget, chunks[5]);
 
     // Session List (filtered)
-    let items: Vec<ListItem> = app
+    let mut items: Vec<ListItem> = app
         .filtered_indices
         .iter()
         .enumerate()
         .map(|(display_idx, &actual_idx)| {
-            let s = &app.sessions[actual_idx];
+            let s = &app.catalog.sessions[actual_idx];
             let (prefix, style) = if display_idx == app.selected {
                 (
                     "▶ ",
@@ -435,25 +1415,68 @@ fn render_full_ui_tall(f: &mut Frame, app: &App, area: Rect, session_list_height
                 Style::default().fg(Color::DarkGray)
             };
 
-            let line = Line::from(vec![
+            let git_style = if display_idx == app.selected {
+                style
+            } else {
+                Style::default().fg(Color::Magenta)
+            };
+
+            let usage_style = if display_idx == app.selected {
+                style
+            } else {
+                Style::default().fg(Color::Cyan)
+            };
+
+            let (number, pin, suffix) = row_marks(app, display_idx, actual_idx);
+            let mark_style = if display_idx == app.selected {
+                style
+            } else {
+                Style::default().fg(Color::Yellow)
+            };
+
+            let mut spans = vec![
                 Span::styled(prefix, style),
+                Span::styled(number, desc_style),
+                Span::styled(pin, mark_style),
                 Span::styled(format!("{:<20}", s.name), name_style),
+                Span::styled(git_column(app, actual_idx), git_style),
+                Span::styled(usage_column(app, actual_idx), usage_style),
                 Span::styled(format!(" {}", s.description), desc_style),
-            ]);
+                Span::styled(suffix, mark_style),
+            ];
+            spans.extend(service_badges(app, actual_idx));
+            let line = Line::from(spans);
 
             ListItem::new(line).style(style)
         })
         .collect();
 
+    items.extend(create_row(app));
     let list = List::new(items).block(
         Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::Blue))
-            .title(format!(" Select a Session ({}/{}) ", app.filtered_indices.len(), app.sessions.len()))
+            .title(format!(
+                " Select a Session ({}/{}) · {}{} ",
+                app.filtered_indices.len(),
+                app.catalog.sessions.len(),
+                app.order_label(),
+                marked_label(app)
+            ))
             .title_style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
     );
 
-    f.render_widget(list, chunks[7]);
+    // The preview takes over the flexible space below the list
+    let list_area = if preview_visible(app, area) {
+        chunks[7].union(chunks[8])
+    } else {
+        chunks[7]
+    };
+    let (list_area, preview_area) = split_preview(app, list_area);
+    f.render_widget(list, list_area);
+    if let Some(preview_area) = preview_area {
+        render_preview(f, app, preview_area);
+    }
 
     // Quote
     let quotes = [
@@ -476,12 +1499,40 @@ fn render_full_ui_tall(f: &mut Frame, app: &App, area: Rect, session_list_height
 
     f.render_widget(quote_widget, chunks[9]);
 
-    // Help line
+    // Help line, or the result of the last action
+    if let Some(status) = &app.status {
+        f.render_widget(status_line(status), chunks[11]);
+        return;
+    }
     let footer = Paragraph::new(Line::from(vec![
         Span::styled("↑↓", Style::default().fg(Color::DarkGray)),
         Span::styled(" navigate  ", Style::default().fg(Color::DarkGray)),
         Span::styled("⏎", Style::default().fg(Color::Blue)),
         Span::styled(" select  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("⇧⏎", Style::default().fg(Color::DarkGray)),
+        Span::styled(" background  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("1-9", Style::default().fg(Color::DarkGray)),
+        Span::styled(" open (normal mode)  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("^o", Style::default().fg(Color::DarkGray)),
+        Span::styled(" back  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("^f", Style::default().fg(Color::DarkGray)),
+        Span::styled(" pin  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("tab", Style::default().fg(Color::DarkGray)),
+        Span::styled(" mark  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("^b", Style::default().fg(Color::DarkGray)),
+        Span::styled(" batch  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("^p", Style::default().fg(Color::DarkGray)),
+        Span::styled(" preview  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("^g", Style::default().fg(Color::DarkGray)),
+        Span::styled(" git  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("^w", Style::default().fg(Color::DarkGray)),
+        Span::styled(" warm  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("^u", Style::default().fg(Color::DarkGray)),
+        Span::styled(" usage  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("^s", Style::default().fg(Color::DarkGray)),
+        Span::styled(" order  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("←→", Style::default().fg(Color::DarkGray)),
+        Span::styled(" map tab  ", Style::default().fg(Color::DarkGray)),
         Span::styled("esc", Style::default().fg(Color::DarkGray)),
         Span::styled(" clear  ", Style::default().fg(Color::DarkGray)),
         Span::styled("q", Style::default().fg(Color::DarkGray)),
@@ -529,7 +1580,7 @@ fn render_full_ui_short(f: &mut Frame, app: &App, area: Rect, session_list_heigh
     // Search input with box
     let search_text = if app.search_query.is_empty() {
         Line::from(vec![
-            Span::styled(" Type to search...", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!(" {}", search_placeholder(app)), Style::default().fg(Color::DarkGray)),
         ])
     } else {
         Line::from(vec![
@@ -551,12 +1602,12 @@ fn render_full_ui_short(f: &mut Frame, app: &App, area: Rect, session_list_heigh
     f.render_widget(search_widget, chunks[3]);
 
     // Session List (filtered)
-    let items: Vec<ListItem> = app
+    let mut items: Vec<ListItem> = app
         .filtered_indices
         .iter()
         .enumerate()
         .map(|(display_idx, &actual_idx)| {
-            let s = &app.sessions[actual_idx];
+            let s = &app.catalog.sessions[actual_idx];
             let (prefix, style) = if display_idx == app.selected {
                 (
                     "▶ ",
@@ -584,155 +1635,696 @@ fn render_full_ui_short(f: &mut Frame, app: &App, area: Rect, session_list_heigh
                 Style::default().fg(Color::DarkGray)
             };
 
-            let line = Line::from(vec![
+            let git_style = if display_idx == app.selected {
+                style
+            } else {
+                Style::default().fg(Color::Magenta)
+            };
+
+            let usage_style = if display_idx == app.selected {
+                style
+            } else {
+                Style::default().fg(Color::Cyan)
+            };
+
+            let (number, pin, suffix) = row_marks(app, display_idx, actual_idx);
+            let mark_style = if display_idx == app.selected {
+                style
+            } else {
+                Style::default().fg(Color::Yellow)
+            };
+
+            let mut spans = vec![
                 Span::styled(prefix, style),
+                Span::styled(number, desc_style),
+                Span::styled(pin, mark_style),
                 Span::styled(format!("{:<20}", s.name), name_style),
+                Span::styled(git_column(app, actual_idx), git_style),
+                Span::styled(usage_column(app, actual_idx), usage_style),
                 Span::styled(format!(" {}", s.description), desc_style),
-            ]);
+                Span::styled(suffix, mark_style),
+            ];
+            spans.extend(service_badges(app, actual_idx));
+            let line = Line::from(spans);
 
             ListItem::new(line).style(style)
         })
         .collect();
 
+    items.extend(create_row(app));
     let list = List::new(items).block(
         Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::Blue))
-            .title(format!(" Select a Session ({}/{}) ", app.filtered_indices.len(), app.sessions.len()))
+            .title(format!(
+                " Select a Session ({}/{}) · {}{} ",
+                app.filtered_indices.len(),
+                app.catalog.sessions.len(),
+                app.order_label(),
+                marked_label(app)
+            ))
             .title_style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
     );
 
-    f.render_widget(list, chunks[4]);
+    let list_area = if preview_visible(app, area) {
+        chunks[4].union(chunks[5])
+    } else {
+        chunks[4]
+    };
+    let (list_area, preview_area) = split_preview(app, list_area);
+    f.render_widget(list, list_area);
+    if let Some(preview_area) = preview_area {
+        render_preview(f, app, preview_area);
+    }
+
+    if let Some(status) = &app.status {
+        let bottom = Rect { y: area.bottom().saturating_sub(1), height: 1.min(area.height), ..area };
+        f.render_widget(status_line(status), bottom);
+    }
+}
+
+fn status_line(status: &str) -> Paragraph<'_> {
+    Paragraph::new(status)
+        .style(Style::default().fg(Color::Yellow))
+        .alignment(Alignment::Center)
+}
+
+fn search_placeholder(app: &App) -> &'static str {
+    match app.mode {
+        Mode::Search => "Type to search...",
+        Mode::Normal => "Normal mode: hotkeys open entries, / to search",
+    }
+}
+
+/// The quick-select number, mark or pin marker and the state and hotkey
+/// suffix shown with a list row.
+fn row_marks(app: &App, display_idx: usize, index: usize) -> (String, &'static str, String) {
+    let number = if display_idx < 9 && app.quick_select() {
+        format!("{} ", display_idx + 1)
+    } else {
+        "  ".to_string()
+    };
+    let pin = if app.marked.contains(&index) {
+        "● "
+    } else if app.is_pinned(index) {
+        "★ "
+    } else {
+        "  "
+    };
+    let session = &app.catalog.sessions[index];
+    let warming = || {
+        let dir = app.working_dir(index)?;
+        app.warmer.state(&dir, env::installable(session))
+    };
+    let state = if app.starting.iter().any(|&(i, ..)| i == index) {
+        "  ◌ starting…"
+    } else if matches!(warming(), Some(warm::State::Queued | warm::State::Building(_))) {
+        "  ◌ warming…"
+    } else if app.instances_of(session).any(|r| !r.exited) {
+        "  ● running"
+    } else {
+        ""
+    };
+    let hotkey = session.key.map(|key| format!("  [{}]", key)).unwrap_or_default();
+    (number, pin, format!("{}{}", state, hotkey))
+}
+
+/// Badges for the services of a list row: green when up, red when down,
+/// grey until the first probe is in.
+fn service_badges(app: &App, index: usize) -> Vec<Span<'static>> {
+    app.catalog.sessions[index]
+        .services
+        .iter()
+        .map(|service| {
+            let color = match app.health.get(service) {
+                Some(health) if health.up => Color::Green,
+                Some(_) => Color::Red,
+                None => Color::DarkGray,
+            };
+            Span::styled(format!("  ●{}", service.label()), Style::default().fg(color))
+        })
+        .collect()
 }
 
-fn cleanup_old_sessions() -> Result<()> {
-    let output = Command::new("zellij")
-        .args(["list-sessions"])
-        .output()
-        .context("Failed to list zellij sessions")?;
+/// The git summary of a list row, if the git column is toggled on (Ctrl-g).
+fn git_summary(app: &App, index: usize) -> Option<String> {
+    if !app.show_git {
+        return None;
+    }
+    let dir = app.working_dir(index)?;
+    if app.git.is_pending(&dir) {
+        return Some("…".to_string());
+    }
+    app.git.get(&dir).map(git::Status::summary)
+}
+
+/// The git column of the full list, padded to a fixed width.
+fn git_column(app: &App, index: usize) -> String {
+    if !app.show_git {
+        return String::new();
+    }
+    let summary = git_summary(app, index).unwrap_or_default();
+    let width = GIT_COLUMN_WIDTH - 1;
+    let summary = if summary.chars().count() > width {
+        format!("{}…", summary.chars().take(width - 1).collect::<String>())
+    } else {
+        summary
+    };
+    format!(" {:<width$}", summary)
+}
 
-    if !output.status.success() {
-        return Ok(()); // No sessions to clean up
+/// The usage summary of a list row, if the usage column is toggled on
+/// (Ctrl-u) or the list is in usage order.
+fn usage_summary(app: &App, index: usize) -> Option<String> {
+    if !app.show_usage && app.order != Order::Usage {
+        return None;
     }
+    app.entry_usage(index).map(|usage| usage.summary())
+}
 
-    let sessions = String::from_utf8_lossy(&output.stdout);
+/// The usage column of the full list, padded to a fixed width.
+fn usage_column(app: &App, index: usize) -> String {
+    if !app.show_usage && app.order != Order::Usage {
+        return String::new();
+    }
+    let summary = usage_summary(app, index).unwrap_or_default();
+    format!(" {:<width$}", summary, width = USAGE_COLUMN_WIDTH - 1)
+}
 
-    // Valid session prefixes
-    let valid_prefixes = [
-        "energyboard-",
-        "easyasset-",
-        "colony-",
-        "backoffice-",
-        "gappel-solutions-",
-        "decon-",
-        "screensaver-",
-        "lazychat-",
-        "elixir-",
-        "msp-",
-        "smartflex-",
-        "lazylink-",
-    ];
+/// The "create session" row below the matches, if the query offers one.
+fn create_row(app: &App) -> Option<ListItem<'static>> {
+    let name = app.create_name()?;
+    let selected = app.on_create_row();
+    let style = if selected {
+        Style::default().fg(Color::Black).bg(Color::Blue).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::Green)
+    };
+    // The minimal list marks the selection by color only
+    let prefix = if selected && !app.minimal { "▶ " } else { "  " };
+    Some(ListItem::new(Line::from(vec![
+        Span::styled(prefix, style),
+        Span::styled(format!("  + create session '{}'", name), style),
+    ])))
+}
 
-    for line in sessions.lines() {
-        // Remove ANSI color codes
-        let clean_line = strip_ansi_codes(line);
+/// The number of marked entries for the list title, if any.
+fn marked_label(app: &App) -> String {
+    if app.marked.is_empty() {
+        String::new()
+    } else {
+        format!(" · {} marked", app.marked.len())
+    }
+}
 
-        // Skip EXITED sessions
-        if clean_line.contains("EXITED") {
-            continue;
+fn preview_visible(app: &App, area: Rect) -> bool {
+    app.show_preview && area.width >= PREVIEW_MIN_WIDTH
+}
+
+/// Splits off the preview pane to the right of `area`, collapsing it
+/// when toggled off or when the terminal is too narrow.
+fn split_preview(app: &App, area: Rect) -> (Rect, Option<Rect>) {
+    if !preview_visible(app, area) {
+        return (area, None);
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(area);
+
+    (chunks[0], Some(chunks[1]))
+}
+
+fn render_preview(f: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray))
+        .title_style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD));
+
+    let Some(idx) = app.selected_index() else {
+        if let Some(name) = app.create_name().filter(|_| app.on_create_row()) {
+            render_create_preview(f, app, &name, block, area);
+        } else {
+            f.render_widget(block.title(" Preview "), area);
         }
+        return;
+    };
+    let session = &app.catalog.sessions[idx];
+
+    let label = Style::default().fg(Color::DarkGray);
+    let heading = Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD);
+    let value = Style::default().fg(Color::White);
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled("Layout  ", label),
+        Span::styled(catalog::contract_home(&app.catalog.layout_path(session)), value),
+    ])];
+
+    let layout = app.layouts[idx].as_ref();
+    let cwd = session
+        .cwd
+        .clone()
+        .or_else(|| layout.ok().and_then(|l| l.cwd.clone()));
+    if let Some(cwd) = cwd {
+        lines.push(Line::from(vec![
+            Span::styled("Cwd     ", label),
+            Span::styled(cwd, value),
+        ]));
+    }
 
-        // Extract session name (first word)
-        if let Some(session_name) = clean_line.split_whitespace().next() {
-            // Check if it matches any valid prefix
-            let is_valid = valid_prefixes.iter().any(|&prefix| session_name.starts_with(prefix));
+    lines.extend(env_preview(app, idx));
+    lines.extend(git_preview(app, idx));
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Tabs", heading)));
+    match layout {
+        Ok(layout) => {
+            // Tabs without commands are collapsed onto a single line
+            let mut plain_tabs: Vec<&str> = Vec::new();
+            for tab in &layout.tabs {
+                if tab.commands.is_empty() {
+                    plain_tabs.push(&tab.name);
+                    continue;
+                }
+                if !plain_tabs.is_empty() {
+                    lines.push(Line::from(Span::styled(format!("  {}", plain_tabs.join(" · ")), value)));
+                    plain_tabs.clear();
+                }
 
-            if !is_valid {
-                // Kill session that doesn't match template
-                let _ = Command::new("zellij")
-                    .args(["delete-session", session_name])
-                    .output();
+                let mut tab_line = vec![Span::styled(format!("  {}", tab.name), value)];
+                if let Some(cwd) = &tab.cwd {
+                    tab_line.push(Span::styled(format!("  {}", cwd), label));
+                }
+                lines.push(Line::from(tab_line));
+
+                for cmd in &tab.commands {
+                    let name = cmd.name.as_deref().unwrap_or("");
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("    {:<10}", name), label),
+                        Span::styled(cmd.display(), Style::default().fg(Color::Yellow)),
+                    ]));
+                }
             }
+            if !plain_tabs.is_empty() {
+                lines.push(Line::from(Span::styled(format!("  {}", plain_tabs.join(" · ")), value)));
+            }
+        }
+        Err(err) => {
+            lines.push(Line::from(Span::styled(format!("  {}", err), Style::default().fg(Color::Red))));
         }
     }
 
-    Ok(())
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Running", heading)));
+    let mut any_running = false;
+    for instance in app.instances_of(session) {
+        any_running = true;
+        let (marker, marker_style) = if instance.exited {
+            ("○ ", Style::default().fg(Color::DarkGray))
+        } else {
+            ("● ", Style::default().fg(Color::Green))
+        };
+        let mut age = match (instance.exited, instance.age) {
+            (true, _) => "exited".to_string(),
+            (false, Some(age)) => zellij::format_age(age),
+            (false, None) => String::new(),
+        };
+        if instance.current {
+            age.push_str(" (current)");
+        }
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}", marker), marker_style),
+            Span::styled(format!("{:<28}", instance.name), value),
+            Span::styled(age, label),
+        ]));
+    }
+    if any_running {
+        lines.push(Line::from(Span::styled("  ^x kill · ^d delete exited · ^r rename · ^e detach clients", label)));
+    } else {
+        lines.push(Line::from(Span::styled("  none", label)));
+    }
+
+    if let Some(usage) = app.entry_usage(idx) {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("Processes  ", heading),
+            Span::styled(
+                format!("{:.1}% cpu · {} · {} processes", usage.cpu, usage::format_bytes(usage.rss), usage.processes),
+                label,
+            ),
+        ]));
+        for process in usage.heaviest.iter().take(5) {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:>6.1}% {:>6}  ", process.cpu, usage::format_bytes(process.rss)), Style::default().fg(Color::Cyan)),
+                Span::styled(process.command.clone(), value),
+            ]));
+        }
+        if !usage.heaviest.is_empty() {
+            lines.push(Line::from(Span::styled("  ^k kill a process", label)));
+        }
+    }
+
+    if !session.services.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Services", heading)));
+        for service in &session.services {
+            let (marker, detail) = match app.health.get(service) {
+                Some(health) if health.up => (Style::default().fg(Color::Green), health.detail.clone()),
+                Some(health) => (Style::default().fg(Color::Red), health.detail.clone()),
+                None => (label, "probing…".to_string()),
+            };
+            lines.push(Line::from(vec![
+                Span::styled("  ● ", marker),
+                Span::styled(format!("{:<29} ", service.display()), value),
+                Span::styled(detail, label),
+            ]));
+        }
+    }
+
+    if let Some(notes) = &session.notes {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Notes", heading)));
+        for note in notes.lines() {
+            lines.push(Line::from(Span::styled(format!("  {}", note), value)));
+        }
+    }
+
+    let inner = block.inner(area);
+    f.render_widget(block.title(format!(" {} ", session.name)), area);
+
+    // The mini-map gets the lower part of the pane when there is room for it
+    let map_height = match layout {
+        Ok(layout) if !layout.tabs.is_empty() && inner.height >= 16 => (inner.height / 2).min(18),
+        _ => 0,
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(map_height)])
+        .split(inner);
+
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), chunks[0]);
+    if let (Ok(layout), true) = (layout, map_height > 0) {
+        render_minimap(f, app, layout, chunks[1]);
+    }
+}
+
+/// The preview's lines on the environment new sessions get and the
+/// project's flake devshell.
+fn env_preview(app: &App, index: usize) -> Vec<Line<'static>> {
+    let label = Style::default().fg(Color::DarkGray);
+    let value = Style::default().fg(Color::White);
+    let session = &app.catalog.sessions[index];
+    let mut lines = Vec::new();
+
+    if session.env_source.is_some() || !session.env.is_empty() {
+        let mut parts: Vec<String> = session.env_source.iter().cloned().collect();
+        if !session.env.is_empty() {
+            let keys: Vec<&str> = session.env.keys().map(String::as_str).collect();
+            parts.push(keys.join(" "));
+        }
+        lines.push(Line::from(vec![
+            Span::styled("Env     ", label),
+            Span::styled(parts.join(" · "), value),
+        ]));
+    }
+
+    let Some(dir) = app.working_dir(index) else { return lines };
+    let installable = env::installable(session);
+    let warming = match app.warmer.state(&dir, installable) {
+        Some(warm::State::Queued) => Some(("devshell queued to warm".to_string(), Color::Yellow)),
+        Some(warm::State::Building(took)) => Some((format!("devshell warming… {}", zellij::format_age(took)), Color::Yellow)),
+        Some(warm::State::Failed(e)) => Some((e.to_string(), Color::Red)),
+        None => None,
+    };
+    let (state, style) = match (env::devshell(&dir, installable), warming) {
+        (env::Devshell::NoFlake, _) => return lines,
+        (_, Some((state, color))) => (state, Style::default().fg(color)),
+        (env::Devshell::NotBuilt, None) => ("devshell not built".to_string(), Style::default().fg(Color::Yellow)),
+        (env::Devshell::Outdated, None) => (
+            "devshell outdated (flake.lock changed)".to_string(),
+            Style::default().fg(Color::Yellow),
+        ),
+        (env::Devshell::Built(at), None) => {
+            let age = std::time::SystemTime::now().duration_since(at).unwrap_or_default();
+            (format!("devshell built {} ago", zellij::format_age(age)), Style::default().fg(Color::Green))
+        }
+    };
+    lines.push(Line::from(vec![
+        Span::styled("Nix     ", label),
+        Span::styled("flake.nix · ", value),
+        Span::styled(state, style),
+    ]));
+    lines
 }
 
-fn strip_ansi_codes(s: &str) -> String {
-    let mut result = String::new();
-    let mut in_escape = false;
+/// The preview's git section, for entries whose working directory is a
+/// repository.
+fn git_preview(app: &App, index: usize) -> Vec<Line<'static>> {
+    let label = Style::default().fg(Color::DarkGray);
+    let value = Style::default().fg(Color::White);
+    let Some(dir) = app.working_dir(index) else { return Vec::new() };
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled("Git", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD))),
+    ];
+    let Some(status) = app.git.get(&dir) else {
+        if !app.git.is_pending(&dir) {
+            return Vec::new();
+        }
+        lines.push(Line::from(Span::styled("  reading…", label)));
+        return lines;
+    };
 
-    for ch in s.chars() {
-        if ch == '\x1b' {
-            in_escape = true;
-        } else if in_escape {
-            if ch == 'm' {
-                in_escape = false;
-            }
-        } else {
-            result.push(ch);
+    let mut branch = vec![
+        Span::styled("  ", label),
+        Span::styled(status.branch.clone().unwrap_or_else(|| "detached HEAD".to_string()), Style::default().fg(Color::Magenta)),
+    ];
+    match &status.upstream {
+        Some(upstream) if status.ahead == 0 && status.behind == 0 => {
+            branch.push(Span::styled(format!("  up to date with {}", upstream), label));
         }
+        Some(upstream) => branch.push(Span::styled(
+            format!("  ↑{} ↓{} vs {}", status.ahead, status.behind, upstream),
+            Style::default().fg(Color::Yellow),
+        )),
+        None => branch.push(Span::styled("  no upstream", label)),
     }
+    lines.push(Line::from(branch));
+
+    let counts = format!(
+        "  {} staged · {} dirty · {} stash{}",
+        status.staged,
+        status.dirty,
+        status.stashes,
+        if status.stashes == 1 { "" } else { "es" }
+    );
+    let clean = status.staged == 0 && status.dirty == 0;
+    lines.push(Line::from(Span::styled(counts, if clean { label } else { Style::default().fg(Color::Yellow) })));
+
+    if let Some(commit) = &status.last_commit {
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {} ago  ", zellij::format_age(commit.age)), label),
+            Span::styled(commit.subject.clone(), value),
+        ]));
+    }
+    lines
+}
 
-    result
+/// What choosing the create row will do.
+fn render_create_preview(f: &mut Frame, app: &App, name: &str, block: Block, area: Rect) {
+    let label = Style::default().fg(Color::DarkGray);
+    let value = Style::default().fg(Color::White);
+    let exists = app.running.iter().any(|r| r.name == name);
+    let cwd = app
+        .catalog
+        .create
+        .resolve_cwd(name)
+        .map(|dir| catalog::contract_home(&dir))
+        .unwrap_or_else(|| "zellij's default".to_string());
+
+    let lines = if exists {
+        vec![Line::from(Span::styled(format!("Switches to the running session {}", name), value))]
+    } else {
+        vec![
+            Line::from(vec![Span::styled("Layout  ", label), Span::styled(app.catalog.create.layout.clone(), value)]),
+            Line::from(vec![Span::styled("Cwd     ", label), Span::styled(cwd, value)]),
+        ]
+    };
+    f.render_widget(
+        Paragraph::new(lines).block(block.title(format!(" {} ", name))),
+        area,
+    );
 }
 
-fn launch_session(layout: &str) -> Result<()> {
-    use std::fs::OpenOptions;
-    use std::io::Write;
+fn render_minimap(f: &mut Frame, app: &App, layout: &layout::LayoutInfo, area: Rect) {
+    let label = Style::default().fg(Color::DarkGray);
+    let tab_idx = app.preview_tab % layout.tabs.len();
+    let tab = &layout.tabs[tab_idx];
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled("Map ", Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+        Span::styled(tab.name.clone(), Style::default().fg(Color::White)),
+        Span::styled(format!(" ({}/{})  ←/→", tab_idx + 1, layout.tabs.len()), label),
+    ])];
+
+    let swap_line = if layout.swap_layouts.is_empty() {
+        None
+    } else {
+        let names = |floating: bool| -> Vec<&str> {
+            layout
+                .swap_layouts
+                .iter()
+                .filter(|s| s.floating == floating)
+                .map(|s| s.name.as_str())
+                .collect()
+        };
+        let mut spans = vec![Span::styled("Swap ", label)];
+        for (kind, names) in [("tiled", names(false)), ("floating", names(true))] {
+            if !names.is_empty() {
+                spans.push(Span::styled(format!(" {}: ", kind), label));
+                spans.push(Span::styled(names.join(", "), Style::default().fg(Color::White)));
+            }
+        }
+        Some(Line::from(spans))
+    };
 
+    let swap_height = swap_line
+        .as_ref()
+        .map_or(0, |line| (line.width() as u16).div_ceil(area.width.max(1)));
+    let map_height = area.height.saturating_sub(1 + swap_height);
+    lines.extend(minimap::render(&tab.root, area.width, map_height));
+    lines.extend(swap_line);
+
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), area);
+}
+
+/// Entries whose instances are never kept by the cleanup
+const EPHEMERAL_SESSIONS: [&str; 2] = ["new", "welcome"];
+
+/// Deletes running sessions that don't belong to a catalog entry or a
+/// discovered project and returns their names. Sessions zellij refuses to
+/// delete are reported on stderr.
+fn cleanup_old_sessions(catalog: &Catalog) -> Vec<String> {
+    let mut catalog = catalog.clone();
+    let projects = discover::entries(&catalog, &discover::load_cache());
+    catalog.sessions.extend(projects);
+
+    let mut deleted = Vec::new();
+    // Skip EXITED sessions
+    for session in zellij::list_sessions().iter().filter(|s| !s.exited) {
+        let is_valid = catalog
+            .sessions
+            .iter()
+            .filter(|s| !EPHEMERAL_SESSIONS.contains(&s.name.as_str()))
+            .any(|s| s.owns(&session.name));
+
+        if !is_valid {
+            // Running sessions are only deleted with --force, which kills them first
+            match zellij::delete_session(&session.name, true) {
+                Ok(()) => deleted.push(session.name.clone()),
+                Err(e) => eprintln!("{:#}", e),
+            }
+        }
+    }
+
+    deleted
+}
+
+fn open_log() -> Result<std::fs::File> {
     let home = std::env::var("HOME")?;
     let log_path = format!("{}/zellij-welcome.log", home);
-    let mut log_file = OpenOptions::new()
+    Ok(std::fs::OpenOptions::new()
         .create(true)
         .append(true)
-        .open(&log_path)?;
-
-    writeln!(log_file, "\n=== {} ===", Local::now().format("%Y-%m-%d %H:%M:%S"))?;
-    writeln!(log_file, "Layout requested: {}", layout)?;
+        .open(&log_path)?)
+}
 
-    let plugin_path = format!(
-        "{}/dev/misc/dotfiles/zellij/.config/zellij/plugins/zellij-switch.wasm",
-        home
-    );
-    writeln!(log_file, "Plugin path: {}", plugin_path)?;
+/// Attaches to a running instance of `session`, or creates one, printing
+/// the output of its hooks.
+fn launch_session(catalog: &Catalog, session: &Session) -> Result<()> {
+    launch_session_with(catalog, session, &hooks::print, &hooks::Cancel::none())
+}
 
-    // Get existing active session (non-EXITED)
-    let output = Command::new("zellij")
-        .args(["list-sessions"])
-        .output()
-        .context("Failed to list sessions")?;
+/// [`launch_session`], reporting hook output to `report`; `cancel` aborts
+/// it while hooks run.
+fn launch_session_with(
+    catalog: &Catalog,
+    session: &Session,
+    report: &(dyn Fn(hooks::Event) + Sync),
+    cancel: &hooks::Cancel,
+) -> Result<()> {
+    use std::io::Write;
 
-    let sessions = String::from_utf8_lossy(&output.stdout);
-    writeln!(log_file, "Sessions output:\n{}", sessions)?;
+    let mut log_file = open_log()?;
+    writeln!(log_file, "\n=== {} ===", Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+    writeln!(log_file, "Layout requested: {}", session.layout_name())?;
 
-    let prefix = format!("{}-", layout);
+    // Get existing active session (non-EXITED)
+    let sessions = zellij::list_sessions();
+    let names: Vec<&str> = sessions.iter().map(|s| s.name.as_str()).collect();
+    writeln!(log_file, "Sessions: {:?}", names)?;
 
     let existing = sessions
-        .lines()
-        .map(|line| strip_ansi_codes(line))
-        .filter(|line| !line.contains("EXITED"))
-        .filter_map(|line| {
-            line.split_whitespace()
-                .next()
-                .map(|s| s.to_string())
-        })
-        .find(|name| name.starts_with(&prefix));
+        .iter()
+        .filter(|s| !s.exited)
+        .find(|s| session.owns(&s.name))
+        .map(|s| s.name.clone());
 
-    writeln!(log_file, "Looking for prefix: {}", prefix)?;
     writeln!(log_file, "Found existing: {:?}", existing)?;
 
-    let session_arg = if let Some(existing_name) = existing {
+    if let Some(existing_name) = existing {
         // Attach to existing
-        format!("-s {}", existing_name)
+        switch_session(catalog, &existing_name, &format!("-s {}", existing_name))
     } else {
-        // Create new with timestamp
-        let timestamp = Local::now().format("%Y%m%d-%H%M%S");
-        let new_name = format!("{}-{}", layout, timestamp);
-        format!("-s {} -l {}", new_name, layout)
-    };
+        // Create a new one, named by the entry's template
+        let new_name = naming::instance_name(catalog, session, &sessions)?;
+        let env = env::resolve(catalog, session)?;
+        hooks::run(catalog, session, &new_name, hooks::Stage::Pre, &env, report, cancel)?;
+        if env.is_empty() {
+            create_and_switch(catalog, &new_name, session.layout_name(), session.launch_cwd().as_deref())?;
+        } else {
+            // The switch plugin can't pass an environment on, so the session
+            // is created from here first and then switched to
+            zellij::create_background(&new_name, session.layout_name(), session.launch_cwd().as_deref(), &env)?;
+            switch_session(catalog, &new_name, &format!("-s {}", new_name))?;
+        }
+        hooks::run(catalog, session, &new_name, hooks::Stage::Post, &env, report, cancel)
+    }
+}
 
+/// Creates `instance` with `layout`, started in `cwd` if given, and switches
+/// to it. The switch plugin splits its arguments on whitespace, so with a
+/// `cwd` containing any the session is created from here first.
+fn create_and_switch(catalog: &Catalog, instance: &str, layout: &str, cwd: Option<&Path>) -> Result<()> {
+    match cwd {
+        Some(cwd) if cwd.to_string_lossy().contains(char::is_whitespace) => {
+            zellij::create_background(instance, layout, Some(cwd), &[])?;
+            switch_session(catalog, instance, &format!("-s {}", instance))
+        }
+        _ => {
+            let mut session_arg = format!("-s {} -l {}", instance, layout);
+            if let Some(cwd) = cwd {
+                session_arg.push_str(&format!(" -c {}", cwd.display()));
+            }
+            switch_session(catalog, instance, &session_arg)
+        }
+    }
+}
+
+/// Switches to (or creates) `instance` via the zellij-switch plugin and
+/// records the switch.
+fn switch_session(catalog: &Catalog, instance: &str, session_arg: &str) -> Result<()> {
+    use std::io::Write;
+
+    let home = std::env::var("HOME")?;
+    let mut log_file = open_log()?;
+
+    let plugin_path = format!(
+        "{}/dev/misc/dotfiles/zellij/.config/zellij/plugins/zellij-switch.wasm",
+        home
+    );
+    writeln!(log_file, "Plugin path: {}", plugin_path)?;
     writeln!(log_file, "Session arg: {}", session_arg)?;
 
     let full_cmd = format!(
@@ -743,7 +2335,7 @@ fn launch_session(layout: &str) -> Result<()> {
 
     // Launch via zellij pipe
     let result = Command::new("zellij")
-        .args(["pipe", "--plugin", &format!("file:{}", plugin_path), "--", &session_arg])
+        .args(["pipe", "--plugin", &format!("file:{}", plugin_path), "--", session_arg])
         .output();
 
     match &result {
@@ -760,5 +2352,15 @@ fn launch_session(layout: &str) -> Result<()> {
 
     result.context("Failed to launch zellij session")?;
 
+    if let Err(e) = tracking::record_switch(catalog, instance) {
+        writeln!(log_file, "Failed to record switch: {:#}", e)?;
+    }
+    let entry = catalog.sessions.iter().find(|s| s.owns(instance));
+    if let Some(entry) = entry {
+        if let Err(e) = history::History::record(&entry.name, instance) {
+            writeln!(log_file, "Failed to record launch: {:#}", e)?;
+        }
+    }
+
     Ok(())
 }
@@ -0,0 +1,213 @@
+//! Renders a layout's pane tree as a scaled box diagram.
+
+use crate::layout::{Pane, PaneSize, SplitDirection};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+/// Terminal size the fixed pane sizes in layouts are scaled against
+const ASSUMED_COLS: u16 = 200;
+const ASSUMED_ROWS: u16 = 50;
+
+#[derive(Clone, Copy)]
+struct Cell {
+    ch: char,
+    label: bool,
+}
+
+struct Canvas {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+#[derive(Clone, Copy)]
+struct Area {
+    x: u16,
+    y: u16,
+    w: u16,
+    h: u16,
+}
+
+pub fn render(root: &Pane, width: u16, height: u16) -> Vec<Line<'static>> {
+    let mut canvas = Canvas {
+        width,
+        height,
+        cells: vec![Cell { ch: ' ', label: false }; width as usize * height as usize],
+    };
+    draw(&mut canvas, root, Area { x: 0, y: 0, w: width, h: height });
+    canvas.into_lines()
+}
+
+fn draw(canvas: &mut Canvas, pane: &Pane, area: Area) {
+    if area.w == 0 || area.h == 0 {
+        return;
+    }
+
+    if pane.children.is_empty() {
+        draw_leaf(canvas, pane.label.as_deref().unwrap_or(""), area);
+        return;
+    }
+
+    if pane.stacked {
+        // One expanded pane with the rest collapsed to their title bars
+        let expanded_h = area.h.saturating_sub(pane.children.len() as u16 - 1).max(1);
+        let mut y = area.y;
+        for (i, child) in pane.children.iter().enumerate() {
+            let h = if i == 0 { expanded_h } else { 1 };
+            if y + h > area.y + area.h {
+                break;
+            }
+            let child_area = Area { x: area.x, y, w: area.w, h };
+            if i == 0 {
+                draw(canvas, child, child_area);
+            } else {
+                draw_leaf(canvas, child.label.as_deref().unwrap_or("…"), child_area);
+            }
+            y += h;
+        }
+        return;
+    }
+
+    let (total, assumed) = match pane.split {
+        SplitDirection::Vertical => (area.w, ASSUMED_COLS),
+        SplitDirection::Horizontal => (area.h, ASSUMED_ROWS),
+    };
+    let lengths = distribute(&pane.children, total, assumed);
+
+    let mut offset = 0;
+    for (child, len) in pane.children.iter().zip(lengths) {
+        let len = len.min(total - offset);
+        let child_area = match pane.split {
+            SplitDirection::Vertical => Area { x: area.x + offset, y: area.y, w: len, h: area.h },
+            SplitDirection::Horizontal => Area { x: area.x, y: area.y + offset, w: area.w, h: len },
+        };
+        draw(canvas, child, child_area);
+        offset += len;
+    }
+}
+
+/// Splits `total` cells among `children` according to their sizes. Fixed
+/// sizes are scaled from an assumed terminal size, percentages apply to the
+/// whole, and unsized panes share what is left.
+fn distribute(children: &[Pane], total: u16, assumed: u16) -> Vec<u16> {
+    let mut lengths: Vec<Option<u16>> = children
+        .iter()
+        .map(|child| match child.size {
+            // Thin bars like compact-bar keep their exact size
+            Some(PaneSize::Fixed(n)) if n <= 2 => Some(n),
+            Some(PaneSize::Fixed(n)) => Some((n as u32 * total as u32 / assumed as u32).max(3) as u16),
+            Some(PaneSize::Percent(p)) => Some((p as u32 * total as u32 / 100) as u16),
+            None => None,
+        })
+        .collect();
+
+    let used: u16 = lengths.iter().flatten().fold(0, |acc, &n| acc.saturating_add(n));
+    let flexible = lengths.iter().filter(|l| l.is_none()).count() as u16;
+    let remaining = total.saturating_sub(used);
+    for length in lengths.iter_mut().filter(|l| l.is_none()) {
+        *length = Some(remaining / flexible);
+    }
+
+    let mut lengths: Vec<u16> = lengths.into_iter().map(|l| l.unwrap_or(0)).collect();
+
+    // Rounding leftovers (or overflow) go to the last non-fixed pane
+    let sum: u16 = lengths.iter().fold(0, |acc, &n| acc.saturating_add(n));
+    let last = children
+        .iter()
+        .rposition(|c| !matches!(c.size, Some(PaneSize::Fixed(_))))
+        .unwrap_or(children.len().saturating_sub(1));
+    if let Some(len) = lengths.get_mut(last) {
+        *len = (*len + total).saturating_sub(sum);
+    }
+
+    lengths
+}
+
+fn draw_leaf(canvas: &mut Canvas, label: &str, area: Area) {
+    if area.h < 2 || area.w < 2 {
+        // Too thin for a box: draw a labelled bar instead
+        for y in area.y..area.y + area.h {
+            for x in area.x..area.x + area.w {
+                canvas.set(x, y, '─', false);
+            }
+        }
+        canvas.label(label, area.x, area.y + area.h / 2, area.w, true);
+        return;
+    }
+
+    let (right, bottom) = (area.x + area.w - 1, area.y + area.h - 1);
+    for x in area.x + 1..right {
+        canvas.set(x, area.y, '─', false);
+        canvas.set(x, bottom, '─', false);
+    }
+    for y in area.y + 1..bottom {
+        canvas.set(area.x, y, '│', false);
+        canvas.set(right, y, '│', false);
+    }
+    canvas.set(area.x, area.y, '┌', false);
+    canvas.set(right, area.y, '┐', false);
+    canvas.set(area.x, bottom, '└', false);
+    canvas.set(right, bottom, '┘', false);
+
+    if area.h > 2 && area.w > 2 {
+        let middle = area.y + (area.h - 1) / 2;
+        canvas.label(label, area.x + 1, middle, area.w - 2, false);
+    }
+}
+
+impl Canvas {
+    fn set(&mut self, x: u16, y: u16, ch: char, label: bool) {
+        if x < self.width && y < self.height {
+            self.cells[y as usize * self.width as usize + x as usize] = Cell { ch, label };
+        }
+    }
+
+    /// Writes `text` centered within `width` cells, truncated with `…`.
+    fn label(&mut self, text: &str, x: u16, y: u16, width: u16, padded: bool) {
+        let text = if padded && !text.is_empty() {
+            format!(" {} ", text)
+        } else {
+            text.to_string()
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let width = width as usize;
+        let shown: Vec<char> = if chars.len() > width {
+            chars[..width.saturating_sub(1)].iter().copied().chain(std::iter::once('…')).take(width).collect()
+        } else {
+            chars
+        };
+
+        let start = x + ((width - shown.len()) / 2) as u16;
+        for (i, ch) in shown.into_iter().enumerate() {
+            self.set(start + i as u16, y, ch, true);
+        }
+    }
+
+    fn into_lines(self) -> Vec<Line<'static>> {
+        let border = Style::default().fg(Color::DarkGray);
+        let label = Style::default().fg(Color::Yellow);
+
+        self.cells
+            .chunks(self.width.max(1) as usize)
+            .map(|row| {
+                // Group runs of equally styled cells into spans
+                let mut spans: Vec<Span<'static>> = Vec::new();
+                let mut run = String::new();
+                let mut run_is_label = false;
+                for cell in row {
+                    if cell.label != run_is_label && !run.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut run), if run_is_label { label } else { border }));
+                    }
+                    run_is_label = cell.label;
+                    run.push(cell.ch);
+                }
+                if !run.is_empty() {
+                    spans.push(Span::styled(run, if run_is_label { label } else { border }));
+                }
+                Line::from(spans)
+            })
+            .collect()
+    }
+}
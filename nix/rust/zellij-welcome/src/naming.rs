@@ -0,0 +1,351 @@
+//! Instance naming templates (`naming` in the catalog), e.g.
+//! `"{name}-{n}"` or `"{name}-{branch}-{date:%m%d}"`.
+//!
+//! | Placeholder     | Expands to                                          |
+//! |-----------------|-----------------------------------------------------|
+//! | `{name}`        | the entry name                                      |
+//! | `{date}`        | today as `%Y%m%d`, or `{date:FORMAT}` (chrono)      |
+//! | `{time}`        | now as `%H%M%S`, or `{time:FORMAT}` (chrono)        |
+//! | `{n}`           | the lowest number ≥ 1 giving an unused name         |
+//! | `{branch}`      | the git branch checked out in the project root      |
+//! | `{host}`        | the short hostname                                  |
+//! | `{anything}`    | the entry's `vars.anything`, else the top-level one |
+//!
+//! A template needs `{name}` or some fixed text besides separators, or it
+//! would claim other entries' sessions. Matching a session name against it
+//! treats the parts that vary between launches as wildcards: digits for
+//! counters and the numeric fields of dates, anything for branches and vars,
+//! so an entry keeps owning its sessions after a branch switch or a var edit.
+//! Names starting with `{name}-` always match too, so sessions from before a
+//! template was set (or renamed ones) aren't taken for strays by `cleanup`.
+
+use crate::catalog::{Catalog, Session};
+use crate::zellij::RunningSession;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use std::fmt::Write;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// What entries without a `naming` template are called, e.g.
+/// `colony-20260203-101500`
+const DEFAULT: &str = "{name}-{date}-{time}";
+
+/// Highest `{n}` tried before giving up
+const MAX_COUNTER: u32 = 999;
+
+enum Part<'a> {
+    Literal(&'a str),
+    Placeholder { key: &'a str, format: Option<&'a str> },
+}
+
+fn parse(template: &str) -> Result<Vec<Part<'_>>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(Part::Literal(&rest[..start]));
+        }
+        let Some(end) = rest[start..].find('}') else {
+            bail!("Unclosed `{{` in naming template `{}`", template);
+        };
+        let inner = &rest[start + 1..start + end];
+        let (key, format) = match inner.split_once(':') {
+            Some((key, format)) => (key, Some(format)),
+            None => (inner, None),
+        };
+        parts.push(Part::Placeholder { key, format });
+        rest = &rest[start + end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Literal(rest));
+    }
+
+    let anchored = parts.iter().any(|part| match part {
+        Part::Literal(text) => text.chars().any(char::is_alphanumeric),
+        Part::Placeholder { key, .. } => *key == "name",
+    });
+    if !anchored {
+        bail!("Naming template `{}` needs `{{name}}` or some fixed text", template);
+    }
+    Ok(parts)
+}
+
+/// A fresh instance name for `session`, not among `running`.
+pub fn instance_name(catalog: &Catalog, session: &Session, running: &[RunningSession]) -> Result<String> {
+    let template = session.naming.as_deref().unwrap_or(DEFAULT);
+    let parts = parse(template)?;
+    let uses_counter = parts
+        .iter()
+        .any(|p| matches!(p, Part::Placeholder { key: "n", .. }));
+
+    // The branch is looked up once, and only when used
+    let mut branch = None;
+    let now = Local::now();
+    let mut render = |n: u32| -> Result<String> {
+        let mut name = String::new();
+        for part in &parts {
+            match part {
+                Part::Literal(text) => name.push_str(text),
+                Part::Placeholder { key, format } => name.push_str(&match *key {
+                    "name" => session.name.clone(),
+                    "date" => format_now(&now, format.unwrap_or("%Y%m%d"))?,
+                    "time" => format_now(&now, format.unwrap_or("%H%M%S"))?,
+                    "n" => n.to_string(),
+                    "branch" => branch.get_or_insert_with(|| git_branch(catalog, session)).clone(),
+                    "host" => hostname(),
+                    var => match session.vars.get(var).or_else(|| catalog.vars.get(var)) {
+                        Some(toml::Value::String(s)) => s.clone(),
+                        Some(value) => value.to_string(),
+                        None => bail!("Unknown placeholder `{{{}}}` in the naming of `{}`", var, session.name),
+                    },
+                }),
+            }
+        }
+        Ok(sanitize(&name))
+    };
+
+    if !uses_counter {
+        return render(0);
+    }
+    for n in 1..=MAX_COUNTER {
+        let name = render(n)?;
+        if !running.iter().any(|r| r.name == name) {
+            return Ok(name);
+        }
+    }
+    bail!("No free `{{n}}` for `{}` below {}", session.name, MAX_COUNTER)
+}
+
+/// Whether `instance` is a name `session`'s template could have produced.
+pub fn matches(session: &Session, instance: &str) -> bool {
+    let legacy = instance
+        .strip_prefix(&session.name)
+        .is_some_and(|rest| rest.starts_with('-'));
+    if legacy {
+        return true;
+    }
+    let Some(template) = &session.naming else { return false };
+    let Ok(parts) = parse(template) else { return false };
+
+    let mut pattern = Vec::new();
+    for part in &parts {
+        match part {
+            Part::Literal(text) => pattern.push(Token::Literal(sanitize(text))),
+            Part::Placeholder { key: "name", .. } => pattern.push(Token::Literal(sanitize(&session.name))),
+            Part::Placeholder { key: "host", .. } => pattern.push(Token::Literal(sanitize(&hostname()))),
+            Part::Placeholder { key: "n", .. } => pattern.push(Token::Digits),
+            Part::Placeholder { key: "date", format } => format_tokens(format.unwrap_or("%Y%m%d"), &mut pattern),
+            Part::Placeholder { key: "time", format } => format_tokens(format.unwrap_or("%H%M%S"), &mut pattern),
+            Part::Placeholder { .. } => pattern.push(Token::Any),
+        }
+    }
+    matches_tokens(&pattern, instance)
+}
+
+enum Token {
+    Literal(String),
+    /// One or more ASCII digits
+    Digits,
+    /// Exactly this many ASCII digits
+    Fixed(usize),
+    /// One or more characters
+    Any,
+}
+
+/// The tokens a chrono `format` renders to: its numeric fields as their
+/// digits, other fields (month names and the like) as wildcards.
+fn format_tokens(format: &str, pattern: &mut Vec<Token>) {
+    let mut literal = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        let spec = if c == '%' { chars.next() } else { None };
+        let token = match spec {
+            None | Some('%') => {
+                literal.push(c);
+                continue;
+            }
+            Some('Y') => Token::Fixed(4),
+            Some('j') => Token::Fixed(3),
+            Some('C' | 'y' | 'm' | 'd' | 'H' | 'I' | 'M' | 'S' | 'U' | 'W' | 'V' | 'G') => Token::Fixed(2),
+            Some('u' | 'w') => Token::Fixed(1),
+            Some('s') => Token::Digits,
+            Some(_) => Token::Any,
+        };
+        if !literal.is_empty() {
+            pattern.push(Token::Literal(sanitize(&std::mem::take(&mut literal))));
+        }
+        match spec {
+            // Shorthands for the fields above
+            Some('F') => format_tokens("%Y-%m-%d", pattern),
+            Some('T') => format_tokens("%H:%M:%S", pattern),
+            Some('R') => format_tokens("%H:%M", pattern),
+            _ => pattern.push(token),
+        }
+    }
+    if !literal.is_empty() {
+        pattern.push(Token::Literal(sanitize(&literal)));
+    }
+}
+
+fn matches_tokens(pattern: &[Token], s: &str) -> bool {
+    let Some((first, rest)) = pattern.split_first() else {
+        return s.is_empty();
+    };
+    match first {
+        Token::Literal(text) => s.strip_prefix(text.as_str()).is_some_and(|s| matches_tokens(rest, s)),
+        Token::Digits => {
+            let digits = s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            (1..=digits).any(|i| matches_tokens(rest, &s[i..]))
+        }
+        Token::Fixed(count) => {
+            let digits = s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            digits >= *count && matches_tokens(rest, &s[*count..])
+        }
+        Token::Any => s
+            .char_indices()
+            .map(|(i, c)| i + c.len_utf8())
+            .any(|end| matches_tokens(rest, &s[end..])),
+    }
+}
+
+fn format_now(now: &DateTime<Local>, format: &str) -> Result<String> {
+    let mut out = String::new();
+    // chrono reports bad format strings as a formatting error
+    if write!(out, "{}", now.format(format)).is_err() {
+        bail!("Invalid date format `{}` in naming template", format);
+    }
+    Ok(out)
+}
+
+/// zellij session names can't contain whitespace or slashes.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_whitespace() || c == '/' { '-' } else { c })
+        .collect()
+}
+
+fn git_branch(catalog: &Catalog, session: &Session) -> String {
+    let Some(dir) = catalog.project_root(session) else { return "nogit".to_string() };
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "nogit".to_string())
+}
+
+fn hostname() -> String {
+    // Matching runs for every row on every frame, so ask only once
+    static HOSTNAME: OnceLock<String> = OnceLock::new();
+    HOSTNAME
+        .get_or_init(|| {
+            Command::new("hostname")
+                .arg("-s")
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                .or_else(|| std::env::var("HOSTNAME").ok())
+                .unwrap_or_else(|| "localhost".to_string())
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(naming: Option<&str>) -> Session {
+        let mut session = Session::new("colony", "");
+        session.naming = naming.map(str::to_string);
+        session
+    }
+
+    fn tokens(format: &str) -> Vec<String> {
+        let mut pattern = Vec::new();
+        format_tokens(format, &mut pattern);
+        pattern
+            .iter()
+            .map(|token| match token {
+                Token::Literal(text) => format!("'{}'", text),
+                Token::Digits => "d+".to_string(),
+                Token::Fixed(n) => format!("d{}", n),
+                Token::Any => "*".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unanchored_templates_are_rejected() {
+        for template in ["{n}", "{branch}-{n}", "{date}_{time}", "-"] {
+            assert!(parse(template).is_err(), "{} should be rejected", template);
+            // And claim no sessions
+            assert!(!matches(&session(Some(template)), "main-1"));
+        }
+        for template in ["{name}", "dev-{n}", "{branch}-x{n}"] {
+            assert!(parse(template).is_ok(), "{} should parse", template);
+        }
+        assert!(parse("{name").is_err());
+    }
+
+    #[test]
+    fn date_shorthands_expand() {
+        assert_eq!(tokens("%F"), ["d4", "'-'", "d2", "'-'", "d2"]);
+        assert_eq!(tokens("%T"), ["d2", "':'", "d2", "':'", "d2"]);
+        assert_eq!(tokens("at %R"), ["'at-'", "d2", "':'", "d2"]);
+        assert_eq!(tokens("%b%d %%"), ["*", "d2", "'-%'"]);
+        assert_eq!(tokens("%s"), ["d+"]);
+
+        let session = session(Some("dev-{date:%F}"));
+        assert!(matches(&session, "dev-2026-01-31"));
+        assert!(!matches(&session, "dev-2026-1-31"));
+        assert!(!matches(&session, "dev-2026-01-311"));
+    }
+
+    #[test]
+    fn fixed_digits_and_counters() {
+        let dated = session(Some("c{date}"));
+        assert!(matches(&dated, "c20260131"));
+        assert!(!matches(&dated, "c2026013"));
+        assert!(!matches(&dated, "c202601311"));
+
+        let counted = session(Some("c{n}"));
+        assert!(matches(&counted, "c1"));
+        assert!(matches(&counted, "c123"));
+        assert!(!matches(&counted, "c"));
+        assert!(!matches(&counted, "c1a"));
+    }
+
+    #[test]
+    fn legacy_prefix_always_matches() {
+        assert!(matches(&session(None), "colony-anything"));
+        assert!(matches(&session(Some("x{n}")), "colony-20260131-101010"));
+        assert!(!matches(&session(None), "colony"));
+        assert!(!matches(&session(None), "colonyx-1"));
+        assert!(!matches(&session(None), "energyboard-1"));
+    }
+
+    #[test]
+    fn any_is_followed_by_a_literal() {
+        let session = session(Some("{branch}@{name}"));
+        assert!(matches(&session, "main@colony"));
+        assert!(matches(&session, "feat-a@b@colony"));
+        assert!(!matches(&session, "@colony"));
+        assert!(!matches(&session, "main@colonyx"));
+        assert!(!matches(&session, "main-colony"));
+    }
+
+    #[test]
+    fn names_render_from_the_template() {
+        let catalog: Catalog = toml::from_str("").unwrap();
+        let running = crate::zellij::parse_sessions("colony-1 [Created 1m ago]\ncolony-2 [Created 1m ago] (EXITED)\n");
+        let name = instance_name(&catalog, &session(Some("{name}-{n}")), &running).unwrap();
+        assert_eq!(name, "colony-3");
+        let name = instance_name(&catalog, &session(Some("{name} / {missing}")), &running);
+        assert!(name.is_err());
+    }
+}
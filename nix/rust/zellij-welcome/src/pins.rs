@@ -0,0 +1,26 @@
+//! Pinned favorites, kept in `~/.local/share/zellij-welcome/pins.json` as
+//! a list of catalog entry names in the order they were pinned.
+
+use crate::catalog;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+pub fn load() -> Vec<String> {
+    std::fs::read_to_string(path())
+        .ok()
+        .and_then(|source| serde_json::from_str(&source).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(pins: &[String]) -> Result<()> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(pins)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn path() -> PathBuf {
+    catalog::data_dir().join("pins.json")
+}
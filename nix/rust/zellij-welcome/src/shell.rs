@@ -0,0 +1,204 @@
+//! `shell-init`: emits shell code for the catalog's aliases, a key binding
+//! that opens the picker, completions for the subcommands and a prompt hook
+//! that feeds time tracking (see `report`).
+//!
+//! ```zsh
+//! eval "$(zellij-welcome shell-init zsh)"
+//! ```
+//!
+//! Completions call back into the hidden `complete` subcommand, so they offer
+//! the sessions that are running at completion time rather than at init time.
+
+use crate::catalog::Catalog;
+use crate::zellij;
+use clap::ValueEnum;
+use std::collections::BTreeSet;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Shell {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Candidates {
+    /// Catalog entry names
+    Entries,
+    /// Running session names plus catalog entry names
+    Sessions,
+}
+
+const BIN: &str = "zellij-welcome";
+
+/// Subcommands whose argument is completed, and with what
+const COMPLETED_ARGS: [(&str, &str); 5] = [
+    ("open", "entries"),
+    ("kill", "sessions"),
+    ("warm", "entries"),
+    ("generate-layouts", "entries"),
+    ("shell-init", "shells"),
+];
+
+pub fn init(catalog: &Catalog, shell: Shell, subcommands: &[String]) -> String {
+    let mut out = format!("# {} shell integration\n\n", BIN);
+
+    for session in &catalog.sessions {
+        let Some(alias) = &session.alias else { continue };
+        if !is_identifier(alias) {
+            eprintln!("Skipping alias `{}` of `{}`: not a valid function name", alias, session.name);
+            continue;
+        }
+        let command = format!("{} open {}", BIN, quote(&session.name));
+        out.push_str(&match shell {
+            Shell::Zsh | Shell::Bash => format!("{}() {{ {}; }}\n", alias, command),
+            Shell::Fish => format!("function {}; {}; end\n", alias, command),
+        });
+    }
+
+    out.push('\n');
+    out.push_str(&match shell {
+        Shell::Zsh => zsh(subcommands),
+        Shell::Bash => bash(subcommands),
+        Shell::Fish => fish(subcommands),
+    });
+    out
+}
+
+/// Prints completion candidates, one per line.
+pub fn complete(catalog: &Catalog, candidates: Candidates) {
+    let mut names: BTreeSet<String> = catalog.sessions.iter().map(|s| s.name.clone()).collect();
+    if let Candidates::Sessions = candidates {
+        names.extend(zellij::list_sessions().into_iter().map(|s| s.name));
+    }
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+fn zsh(subcommands: &[String]) -> String {
+    let mut cases = String::new();
+    for (subcommand, candidates) in COMPLETED_ARGS {
+        let words = match candidates {
+            "shells" => "zsh bash fish".to_string(),
+            _ => format!("${{(f)\"$({} complete {} 2>/dev/null)\"}}", BIN, candidates),
+        };
+        cases.push_str(&format!("    {}) compadd -- {} ;;\n", subcommand, words));
+    }
+
+    format!(
+        r#"# Alt-z opens the picker on the previous session; pressed again, it steps further back
+_zellij_welcome_widget() {{
+  zle -I
+  {bin} pick --mru </dev/tty
+  zle reset-prompt
+}}
+zle -N _zellij_welcome_widget
+bindkey '^[z' _zellij_welcome_widget
+
+# Activity heartbeat for `{bin} report`
+_zellij_welcome_track() {{
+  [[ -n $ZELLIJ_SESSION_NAME ]] && {{ {bin} track &>/dev/null &! }}
+}}
+precmd_functions+=(_zellij_welcome_track)
+
+_zellij_welcome() {{
+  if (( CURRENT == 2 )); then
+    compadd -- {subcommands}
+    return
+  fi
+  case $words[2] in
+{cases}  esac
+}}
+(( $+functions[compdef] )) && compdef _zellij_welcome {bin}
+"#,
+        bin = BIN,
+        subcommands = subcommands.join(" "),
+        cases = cases,
+    )
+}
+
+fn bash(subcommands: &[String]) -> String {
+    let mut cases = String::new();
+    for (subcommand, candidates) in COMPLETED_ARGS {
+        let words = match candidates {
+            "shells" => "zsh bash fish".to_string(),
+            _ => format!("$({} complete {} 2>/dev/null)", BIN, candidates),
+        };
+        cases.push_str(&format!(
+            "    {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;\n",
+            subcommand, words
+        ));
+    }
+
+    format!(
+        r#"# Alt-z opens the picker on the previous session; pressed again, it steps further back
+[[ $- == *i* ]] && bind -x '"\ez": {bin} pick --mru'
+
+# Activity heartbeat for `{bin} report`
+_zellij_welcome_track() {{
+  [[ -n $ZELLIJ_SESSION_NAME ]] && ( {bin} track &>/dev/null & )
+}}
+PROMPT_COMMAND="_zellij_welcome_track${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+
+_zellij_welcome() {{
+  local cur=${{COMP_WORDS[COMP_CWORD]}}
+  if (( COMP_CWORD == 1 )); then
+    COMPREPLY=($(compgen -W "{subcommands}" -- "$cur"))
+    return
+  fi
+  case ${{COMP_WORDS[1]}} in
+{cases}  esac
+}}
+complete -F _zellij_welcome {bin}
+"#,
+        bin = BIN,
+        subcommands = subcommands.join(" "),
+        cases = cases,
+    )
+}
+
+fn fish(subcommands: &[String]) -> String {
+    let mut out = format!(
+        r#"# Alt-z opens the picker on the previous session; pressed again, it steps further back
+bind \ez '{bin} pick --mru; commandline -f repaint'
+
+# Activity heartbeat for `{bin} report`
+function _zellij_welcome_track --on-event fish_prompt
+    set -q ZELLIJ_SESSION_NAME; and {bin} track &>/dev/null &
+end
+
+complete -c {bin} -f
+complete -c {bin} -n __fish_use_subcommand -a '{subcommands}'
+"#,
+        bin = BIN,
+        subcommands = subcommands.join(" "),
+    );
+    for (subcommand, candidates) in COMPLETED_ARGS {
+        let words = match candidates {
+            "shells" => "zsh bash fish".to_string(),
+            _ => format!("({} complete {} 2>/dev/null)", BIN, candidates),
+        };
+        out.push_str(&format!(
+            "complete -c {} -n '__fish_seen_subcommand_from {}' -a '{}'\n",
+            BIN, subcommand, words
+        ));
+    }
+    out
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with('-')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Single-quotes `s` unless it is a plain word. The result is valid in
+/// POSIX shells and fish alike.
+fn quote(s: &str) -> String {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\"'\"'"))
+    }
+}
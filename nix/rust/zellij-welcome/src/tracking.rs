@@ -0,0 +1,366 @@
+//! Time tracking: switch-in/switch-out events per session, plus activity
+//! heartbeats from the shell prompt (see `shell-init`), kept in
+//! `~/.local/share/zellij-welcome/events.jsonl`. `report` turns them into
+//! time per project.
+//!
+//! Time between two consecutive events is credited to the session that was
+//! active, unless the gap exceeds the idle threshold, in which case it is
+//! treated as time away and dropped.
+
+use crate::catalog::{self, Catalog};
+use crate::zellij::{self, RunningSession};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Heartbeats closer together than this (for the same session) are skipped
+const HEARTBEAT_INTERVAL_SECS: i64 = 60;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    In,
+    Out,
+    Active,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Event {
+    pub timestamp: i64,
+    pub kind: EventKind,
+    /// zellij session name
+    pub session: String,
+    /// Catalog entry the session belongs to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Period {
+    Day,
+    Week,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct Row {
+    period: String,
+    project: String,
+    seconds: i64,
+}
+
+/// Records leaving the current session (see [`current_session`]) and
+/// entering `instance`.
+pub fn record_switch(catalog: &Catalog, instance: &str) -> Result<()> {
+    let now = chrono::Utc::now().timestamp();
+    let current = current_session(&zellij::list_sessions());
+
+    let mut events = Vec::new();
+    match current {
+        Some(current) if current == instance => {
+            events.push(event(catalog, now, EventKind::Active, instance));
+        }
+        Some(current) => {
+            events.push(event(catalog, now, EventKind::Out, &current));
+            events.push(event(catalog, now, EventKind::In, instance));
+        }
+        None => events.push(event(catalog, now, EventKind::In, instance)),
+    }
+    append(&events)
+}
+
+/// Records a heartbeat for the session this process runs in.
+pub fn heartbeat(catalog: &Catalog) -> Result<()> {
+    let Some(session) = current_session(&zellij::list_sessions()) else {
+        return Ok(());
+    };
+
+    // Shell prompts fire often; only the first beat per interval is kept
+    let now = chrono::Utc::now().timestamp();
+    let marker = catalog::data_dir().join("heartbeat");
+    if let Ok(last) = std::fs::read_to_string(&marker) {
+        if let Some((timestamp, name)) = last.split_once(' ') {
+            let recent = timestamp.parse::<i64>().is_ok_and(|t| now - t < HEARTBEAT_INTERVAL_SECS);
+            if recent && name == session {
+                return Ok(());
+            }
+        }
+    }
+
+    append(&[event(catalog, now, EventKind::Active, &session)])?;
+    std::fs::write(&marker, format!("{} {}", now, session))
+        .with_context(|| format!("Failed to write {}", marker.display()))
+}
+
+/// The session this process runs in, from zellij's environment or else the
+/// `(current)` marker.
+pub fn current_session(running: &[RunningSession]) -> Option<String> {
+    match std::env::var("ZELLIJ_SESSION_NAME") {
+        Ok(name) if !name.is_empty() => Some(name),
+        _ => running.iter().find(|s| s.current).map(|s| s.name.clone()),
+    }
+}
+
+/// Sessions with the time they were last entered, left or active, most
+/// recent first.
+pub fn recent_sessions() -> Vec<(String, i64)> {
+    let mut recent: Vec<(String, i64)> = Vec::new();
+    // Events are sorted stably, so of a simultaneous out/in the in comes first
+    for event in load().into_iter().rev() {
+        if !recent.iter().any(|(name, _)| *name == event.session) {
+            recent.push((event.session, event.timestamp));
+        }
+    }
+    recent
+}
+
+/// The most recently used session other than the current one that is still
+/// running.
+pub fn previous_session(running: &[RunningSession]) -> Option<String> {
+    let current = current_session(running);
+    recent_sessions()
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| Some(name) != current.as_ref())
+        .find(|name| running.iter().any(|r| r.name == *name && !r.exited))
+}
+
+fn event(catalog: &Catalog, timestamp: i64, kind: EventKind, session: &str) -> Event {
+    Event {
+        timestamp,
+        kind,
+        session: session.to_string(),
+        entry: catalog
+            .sessions
+            .iter()
+            .find(|s| s.owns(session))
+            .map(|s| s.name.clone()),
+    }
+}
+
+fn append(events: &[Event]) -> Result<()> {
+    let path = path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let mut out = String::new();
+    for event in events {
+        out.push_str(&serde_json::to_string(event)?);
+        out.push('\n');
+    }
+
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(out.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn load() -> Vec<Event> {
+    let mut events: Vec<Event> = std::fs::read_to_string(path())
+        .map(|source| {
+            source
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    events.sort_by_key(|e| e.timestamp);
+    events
+}
+
+fn path() -> PathBuf {
+    catalog::data_dir().join("events.jsonl")
+}
+
+/// Prints time per project and period since `since` (inclusive).
+pub fn report(
+    catalog: &Catalog,
+    period: Period,
+    format: Format,
+    since: Option<NaiveDate>,
+    idle_minutes: Option<u64>,
+) -> Result<()> {
+    let idle_secs = idle_minutes.unwrap_or(catalog.tracking.idle_minutes) as i64 * 60;
+    let since = since
+        .and_then(|d| Local.from_local_datetime(&d.and_hms_opt(0, 0, 0)?).earliest())
+        .map(|d| d.timestamp());
+    let rows = totals(&load(), period, since, idle_secs);
+
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        Format::Csv => {
+            println!("period,project,seconds,hours");
+            for row in &rows {
+                println!("{},{},{},{:.2}", row.period, row.project, row.seconds, row.seconds as f64 / 3600.0);
+            }
+        }
+        Format::Table => print_table(&rows),
+    }
+    Ok(())
+}
+
+/// Time per period and project in `events` (sorted by time), counting gaps
+/// of up to `idle_secs` that start at `since` or later.
+fn totals(events: &[Event], period: Period, since: Option<i64>, idle_secs: i64) -> Vec<Row> {
+    let mut totals: BTreeMap<(String, String), i64> = BTreeMap::new();
+    let mut active: Option<(&Event, i64)> = None;
+
+    for event in events {
+        if let Some((current, start)) = active {
+            let gap = event.timestamp - start;
+            if gap <= idle_secs && since.is_none_or(|s| start >= s) {
+                let project = current.entry.clone().unwrap_or_else(|| current.session.clone());
+                *totals.entry((period_label(start, period), project)).or_default() += gap;
+            }
+        }
+
+        active = match event.kind {
+            EventKind::In | EventKind::Active => Some((event, event.timestamp)),
+            EventKind::Out => match active {
+                Some((current, _)) if current.session == event.session => None,
+                // The time so far was credited above
+                other => other.map(|(current, _)| (current, event.timestamp)),
+            },
+        };
+    }
+
+    totals
+        .into_iter()
+        .filter(|(_, seconds)| *seconds > 0)
+        .map(|((period, project), seconds)| Row { period, project, seconds })
+        .collect()
+}
+
+fn period_label(timestamp: i64, period: Period) -> String {
+    let date = DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.with_timezone(&Local).date_naive())
+        .unwrap_or_default();
+    match period {
+        Period::Day => date.format("%Y-%m-%d").to_string(),
+        Period::Week => {
+            let week = date.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+    }
+}
+
+fn print_table(rows: &[Row]) {
+    if rows.is_empty() {
+        println!("No tracked time.");
+        return;
+    }
+
+    let width = rows.iter().map(|r| r.project.len()).max().unwrap_or(0).max(5);
+    let mut current = None;
+    let mut period_total = 0;
+    for row in rows {
+        if current != Some(&row.period) {
+            if current.is_some() {
+                println!("  {:<width$}  {:>7}\n", "total", hours(period_total), width = width);
+            }
+            println!("{}", row.period);
+            current = Some(&row.period);
+            period_total = 0;
+        }
+        println!("  {:<width$}  {:>7}", row.project, hours(row.seconds), width = width);
+        period_total += row.seconds;
+    }
+    println!("  {:<width$}  {:>7}", "total", hours(period_total), width = width);
+}
+
+fn hours(seconds: i64) -> String {
+    format!("{}h {:02}m", seconds / 3600, seconds / 60 % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2026-01-07, 12:00 UTC: the same day and week in any
+    /// timezone within ±11 hours
+    const NOON: i64 = 1_767_787_200;
+
+    fn event(timestamp: i64, kind: EventKind, session: &str, entry: Option<&str>) -> Event {
+        Event {
+            timestamp,
+            kind,
+            session: session.to_string(),
+            entry: entry.map(str::to_string),
+        }
+    }
+
+    fn row(period: &str, project: &str, seconds: i64) -> Row {
+        Row {
+            period: period.to_string(),
+            project: project.to_string(),
+            seconds,
+        }
+    }
+
+    #[test]
+    fn periods() {
+        assert_eq!(period_label(NOON, Period::Day), "2026-01-07");
+        assert_eq!(period_label(NOON, Period::Week), "2026-W02");
+        assert_eq!(period_label(NOON + 5 * 86_400, Period::Week), "2026-W03");
+    }
+
+    #[test]
+    fn time_goes_to_the_active_entry() {
+        let events = [
+            event(NOON, EventKind::In, "colony-1", Some("colony")),
+            event(NOON + 600, EventKind::Active, "colony-2", Some("colony")),
+            event(NOON + 900, EventKind::Out, "colony-2", Some("colony")),
+            event(NOON + 900, EventKind::In, "stray", None),
+            event(NOON + 1200, EventKind::Active, "stray", None),
+        ];
+        assert_eq!(
+            totals(&events, Period::Day, None, 3600),
+            [row("2026-01-07", "colony", 900), row("2026-01-07", "stray", 300)]
+        );
+    }
+
+    #[test]
+    fn idle_gaps_and_time_before_since_are_dropped() {
+        let events = [
+            event(NOON, EventKind::In, "colony-1", Some("colony")),
+            event(NOON + 300, EventKind::Active, "colony-1", Some("colony")),
+            // Away for longer than the threshold
+            event(NOON + 300 + 901, EventKind::Active, "colony-1", Some("colony")),
+            event(NOON + 1500, EventKind::Active, "colony-1", Some("colony")),
+        ];
+        let week = |since| totals(&events, Period::Week, since, 900);
+        assert_eq!(week(None), [row("2026-W02", "colony", 300 + 299)]);
+        assert_eq!(week(Some(NOON + 1)), [row("2026-W02", "colony", 299)]);
+        assert!(week(Some(NOON + 2000)).is_empty());
+    }
+
+    #[test]
+    fn leaving_another_session_keeps_the_active_one() {
+        let events = [
+            event(NOON, EventKind::In, "a", None),
+            event(NOON + 60, EventKind::Out, "b", None),
+            event(NOON + 120, EventKind::Out, "a", None),
+            event(NOON + 600, EventKind::Active, "a", None),
+        ];
+        assert_eq!(totals(&events, Period::Day, None, 3600), [row("2026-01-07", "a", 120)]);
+    }
+
+    #[test]
+    fn hours_format() {
+        assert_eq!(hours(0), "0h 00m");
+        assert_eq!(hours(3 * 3600 + 7 * 60 + 59), "3h 07m");
+    }
+}
@@ -0,0 +1,248 @@
+//! Resource usage of running sessions, read from `/proc` (Linux only; other
+//! systems show none). Each session's server is the `zellij --server
+//! <socket dir>/<session>` process; its descendants are the panes' shells
+//! and everything they started. A worker thread samples every process
+//! twice per round to turn CPU time into a percentage.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+/// Time between the two samples of a round, and between rounds
+const INTERVAL: Duration = Duration::from_secs(2);
+/// Clock ticks per second of `/proc/<pid>/stat` times (`USER_HZ`, 100 on Linux)
+const TICKS_PER_SEC: f64 = 100.0;
+/// Child processes kept per session, heaviest first
+const HEAVIEST: usize = 8;
+
+#[derive(Clone)]
+pub struct Process {
+    pub pid: u32,
+    pub command: String,
+    /// Percent of one core over the last round
+    pub cpu: f64,
+    /// Resident memory in bytes
+    pub rss: u64,
+}
+
+/// The totals of a process tree
+#[derive(Clone, Default)]
+pub struct Usage {
+    pub cpu: f64,
+    pub rss: u64,
+    pub processes: usize,
+    /// The heaviest processes below the server, by CPU, then memory
+    pub heaviest: Vec<Process>,
+}
+
+impl Usage {
+    /// Compact form for the list column, e.g. `12.3% 340M 17p`.
+    pub fn summary(&self) -> String {
+        format!("{:.1}% {} {}p", self.cpu, format_bytes(self.rss), self.processes)
+    }
+
+    /// Adds up the usage of several sessions.
+    pub fn total<'a>(usages: impl IntoIterator<Item = &'a Usage>) -> Option<Usage> {
+        let mut total: Option<Usage> = None;
+        for usage in usages {
+            let sum = total.get_or_insert_with(Usage::default);
+            sum.cpu += usage.cpu;
+            sum.rss += usage.rss;
+            sum.processes += usage.processes;
+            sum.heaviest.extend(usage.heaviest.iter().cloned());
+        }
+        if let Some(total) = &mut total {
+            sort_heaviest(&mut total.heaviest);
+        }
+        total
+    }
+}
+
+fn sort_heaviest(processes: &mut Vec<Process>) {
+    processes.sort_by(|a, b| b.cpu.total_cmp(&a.cpu).then(b.rss.cmp(&a.rss)));
+    processes.truncate(HEAVIEST);
+}
+
+/// Usage per session name, refreshed by the worker thread.
+pub struct Monitor {
+    usage: HashMap<String, Usage>,
+    rx: Option<Receiver<HashMap<String, Usage>>>,
+}
+
+impl Monitor {
+    /// Starts sampling, where there is a `/proc` to read.
+    pub fn start() -> Self {
+        let rx = Path::new("/proc/self/stat").exists().then(|| {
+            let (tx, rx) = mpsc::channel();
+            std::thread::spawn(move || loop {
+                // Stops once the picker is gone
+                if tx.send(sample()).is_err() {
+                    break;
+                }
+            });
+            rx
+        });
+        Self { usage: HashMap::new(), rx }
+    }
+
+    /// Takes in the latest round; returns whether one finished since the
+    /// last call.
+    pub fn poll(&mut self) -> bool {
+        let Some(rx) = &self.rx else { return false };
+        let mut updated = false;
+        while let Ok(usage) = rx.try_recv() {
+            self.usage = usage;
+            updated = true;
+        }
+        updated
+    }
+
+    pub fn get(&self, session: &str) -> Option<&Usage> {
+        self.usage.get(session)
+    }
+}
+
+struct Stat {
+    ppid: u32,
+    /// User plus system time, in clock ticks
+    ticks: u64,
+}
+
+/// The `ppid` and CPU time of every process.
+fn stats() -> HashMap<u32, Stat> {
+    let Ok(entries) = std::fs::read_dir("/proc") else { return HashMap::new() };
+    entries
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| Some((pid, stat(pid)?)))
+        .collect()
+}
+
+fn stat(pid: u32) -> Option<Stat> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // `pid (comm) state ppid …`; comm may contain spaces and parentheses
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 2..].split(' ').collect();
+    let field = |i: usize| fields.get(i)?.parse::<u64>().ok();
+    Some(Stat {
+        ppid: field(1)? as u32,
+        // utime and stime, fields 14 and 15 of the whole line
+        ticks: field(11)? + field(12)?,
+    })
+}
+
+fn cmdline(pid: u32) -> Vec<String> {
+    std::fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|raw| {
+            raw.split(|&b| b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Resident memory from `VmRSS`, which is in kB whatever the page size.
+fn rss(pid: u32) -> u64 {
+    std::fs::read_to_string(format!("/proc/{}/status", pid))
+        .ok()
+        .and_then(|status| {
+            let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+            line.split_whitespace().nth(1)?.parse::<u64>().ok()
+        })
+        .map_or(0, |kb| kb * 1024)
+}
+
+/// The session a zellij server process serves, from its socket path.
+fn server_session(pid: u32) -> Option<String> {
+    let args = cmdline(pid);
+    let socket = PathBuf::from(args.iter().skip_while(|a| *a != "--server").nth(1)?);
+    // The binary or the socket dir (`/run/user/<uid>/zellij/<version>/`)
+    // gives it away, whatever wraps it
+    let zellij = |path: &Path| path.iter().any(|c| c.to_string_lossy().starts_with("zellij"));
+    if !zellij(Path::new(&args[0])) && !zellij(&socket) {
+        return None;
+    }
+    Some(socket.file_name()?.to_string_lossy().into_owned())
+}
+
+/// One round: two snapshots `INTERVAL` apart, added up per session.
+fn sample() -> HashMap<String, Usage> {
+    let before = stats();
+    let started = Instant::now();
+    std::thread::sleep(INTERVAL);
+    let after = stats();
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (&pid, stat) in &after {
+        children.entry(stat.ppid).or_default().push(pid);
+    }
+    let cpu = |pid: u32| {
+        let now = after.get(&pid).map_or(0, |s| s.ticks);
+        // Processes started during the round count from zero
+        let then = before.get(&pid).map_or(0, |s| s.ticks);
+        now.saturating_sub(then) as f64 / TICKS_PER_SEC / elapsed * 100.0
+    };
+
+    let mut sessions = HashMap::new();
+    for &server in after.keys() {
+        let Some(session) = server_session(server) else { continue };
+        let mut usage = Usage {
+            cpu: cpu(server),
+            rss: rss(server),
+            processes: 1,
+            heaviest: Vec::new(),
+        };
+        let mut stack = children.get(&server).cloned().unwrap_or_default();
+        while let Some(pid) = stack.pop() {
+            let process = Process {
+                pid,
+                command: cmdline(pid).join(" "),
+                cpu: cpu(pid),
+                rss: rss(pid),
+            };
+            usage.cpu += process.cpu;
+            usage.rss += process.rss;
+            usage.processes += 1;
+            // Kernel threads and zombies have no command line
+            if !process.command.is_empty() {
+                usage.heaviest.push(process);
+            }
+            stack.extend(children.get(&pid).into_iter().flatten());
+        }
+        sort_heaviest(&mut usage.heaviest);
+        sessions.insert(session, usage);
+    }
+    sessions
+}
+
+/// `340M`, `1.2G` and the like.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 && unit > 0 {
+        format!("{:.1}{}", value, UNITS[unit])
+    } else {
+        format!("{:.0}{}", value, UNITS[unit])
+    }
+}
+
+/// Sends `SIGTERM` to process `pid`.
+pub fn kill(pid: u32) -> Result<()> {
+    let output = Command::new("kill")
+        .arg(pid.to_string())
+        .output()
+        .context("Failed to run kill")?;
+    if !output.status.success() {
+        bail!("kill {} failed: {}", pid, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
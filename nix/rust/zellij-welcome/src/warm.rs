@@ -0,0 +1,214 @@
+//! Pre-building the nix devshells of flake-based entries, so opening them
+//! after a flake update doesn't wait for `nix develop` in every pane. Each
+//! build runs `nix develop <installable> --command true` in the project root
+//! and then caches the devshell's environment (see [`env`]). Builds go one at
+//! a time, on a worker thread when started from the picker.
+
+use crate::catalog::{Catalog, Session};
+use crate::discover;
+use crate::env::{self, Devshell};
+use crate::zellij;
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// A devshell to build
+pub struct Target {
+    /// The first entry using it, for messages
+    pub entry: String,
+    pub dir: PathBuf,
+    pub installable: String,
+}
+
+impl Target {
+    /// The devshell of `session`, if its project root `dir` has a flake.
+    pub fn new(session: &Session, dir: PathBuf) -> Option<Self> {
+        dir.join("flake.nix").exists().then(|| Self {
+            entry: session.name.clone(),
+            installable: env::installable(session).to_string(),
+            dir,
+        })
+    }
+
+    fn key(&self) -> (PathBuf, String) {
+        (self.dir.clone(), self.installable.clone())
+    }
+
+    pub fn is_outdated(&self) -> bool {
+        matches!(env::devshell(&self.dir, &self.installable), Devshell::Outdated)
+    }
+
+    /// Builds it, returning how long that took.
+    fn build(&self) -> Result<Duration> {
+        let started = Instant::now();
+        env::warm(&self.dir, &self.installable)?;
+        Ok(started.elapsed())
+    }
+}
+
+/// The devshells of `sessions` with a flake, once each.
+pub fn targets<'a>(catalog: &Catalog, sessions: impl IntoIterator<Item = &'a Session>) -> Vec<Target> {
+    let mut targets: Vec<Target> = Vec::new();
+    for session in sessions {
+        let Some(target) = catalog.project_root(session).and_then(|dir| Target::new(session, dir)) else {
+            continue;
+        };
+        if !targets.iter().any(|t| t.key() == target.key()) {
+            targets.push(target);
+        }
+    }
+    targets
+}
+
+/// Builds the devshells of the entries called `names`, or of every entry and
+/// discovered project with a flake. Fails if any build failed, after trying
+/// them all.
+pub fn run(catalog: &Catalog, names: &[String]) -> Result<()> {
+    let targets = if names.is_empty() {
+        let mut catalog = catalog.clone();
+        let projects = discover::entries(&catalog, &discover::load_cache());
+        catalog.sessions.extend(projects);
+        targets(&catalog, &catalog.sessions)
+    } else {
+        let mut sessions = Vec::new();
+        for name in names {
+            let session = crate::cli::find(catalog, name)?;
+            if targets(catalog, [session]).is_empty() {
+                bail!("`{}` has no flake.nix in its project root", name);
+            }
+            sessions.push(session);
+        }
+        targets(catalog, sessions)
+    };
+    if targets.is_empty() {
+        println!("No entry has a flake.nix in its project root");
+        return Ok(());
+    }
+    build_all(&targets)
+}
+
+/// Builds `targets` one after another, printing progress.
+pub fn build_all(targets: &[Target]) -> Result<()> {
+    let mut failed = 0;
+    for target in targets {
+        eprintln!("warming {} ({} in {})", target.entry, target.installable, target.dir.display());
+        match target.build() {
+            Ok(took) => println!("warmed {} in {}", target.entry, zellij::format_age(took)),
+            Err(e) => {
+                eprintln!("{}: {:#}", target.entry, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{} devshell(s) failed to build", failed);
+    }
+    Ok(())
+}
+
+/// What the picker shows for a devshell
+pub enum State<'a> {
+    Queued,
+    Building(Duration),
+    Failed(&'a str),
+}
+
+enum Report {
+    Started((PathBuf, String)),
+    Finished(Target, Result<Duration, String>),
+}
+
+/// The picker's builds: queued ones go to a single worker thread, started
+/// with the first request.
+pub struct Warmer {
+    /// Queued devshells, and when the building one started
+    active: HashMap<(PathBuf, String), Option<Instant>>,
+    /// Devshells whose last build failed, with the error
+    failed: HashMap<(PathBuf, String), String>,
+    jobs: Option<Sender<Target>>,
+    tx: Sender<Report>,
+    rx: Receiver<Report>,
+}
+
+impl Warmer {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            active: HashMap::new(),
+            failed: HashMap::new(),
+            jobs: None,
+            tx,
+            rx,
+        }
+    }
+
+    /// Queues the targets not queued or building yet; returns how many that
+    /// were.
+    pub fn request(&mut self, targets: impl IntoIterator<Item = Target>) -> usize {
+        let jobs = self.jobs.get_or_insert_with(|| {
+            let (jobs, queue) = mpsc::channel::<Target>();
+            let tx = self.tx.clone();
+            std::thread::spawn(move || {
+                for target in queue {
+                    if tx.send(Report::Started(target.key())).is_err() {
+                        break;
+                    }
+                    let result = target.build().map_err(|e| format!("{:#}", e));
+                    if tx.send(Report::Finished(target, result)).is_err() {
+                        break;
+                    }
+                }
+            });
+            jobs
+        });
+
+        let mut queued = 0;
+        for target in targets {
+            let key = target.key();
+            if self.active.contains_key(&key) || jobs.send(target).is_err() {
+                continue;
+            }
+            self.failed.remove(&key);
+            self.active.insert(key, None);
+            queued += 1;
+        }
+        queued
+    }
+
+    /// Takes in what the worker did since the last call, returning a
+    /// message per finished build.
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut messages = Vec::new();
+        while let Ok(report) = self.rx.try_recv() {
+            match report {
+                Report::Started(key) => {
+                    self.active.insert(key, Some(Instant::now()));
+                }
+                Report::Finished(target, result) => {
+                    let key = target.key();
+                    self.active.remove(&key);
+                    messages.push(match result {
+                        Ok(took) => format!("{}: devshell warmed in {}", target.entry, zellij::format_age(took)),
+                        Err(e) => {
+                            let message = format!("{}: {}", target.entry, e);
+                            self.failed.insert(key, e);
+                            message
+                        }
+                    });
+                }
+            }
+        }
+        messages
+    }
+
+    pub fn state(&self, dir: &Path, installable: &str) -> Option<State<'_>> {
+        let key = (dir.to_path_buf(), installable.to_string());
+        match self.active.get(&key) {
+            Some(Some(started)) => Some(State::Building(started.elapsed())),
+            Some(None) => Some(State::Queued),
+            None => self.failed.get(&key).map(|e| State::Failed(e)),
+        }
+    }
+}
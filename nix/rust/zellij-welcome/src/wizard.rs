@@ -0,0 +1,352 @@
+//! The form shown when choosing `new`: a session name, a layout from the
+//! layouts dir, a working directory (Tab completes paths) and an optional
+//! description, optionally saved as a catalog entry.
+
+use crate::catalog::{self, Session};
+use crate::naming;
+use crate::{App, Picked};
+use chrono::Local;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::path::{Path, PathBuf};
+
+/// Directory candidates listed under the cwd field
+const MAX_COMPLETIONS: usize = 5;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Layout,
+    Cwd,
+    Description,
+    Save,
+}
+
+const FIELDS: [Field; 5] = [Field::Name, Field::Layout, Field::Cwd, Field::Description, Field::Save];
+
+pub struct Wizard {
+    field: Field,
+    name: String,
+    layouts: Vec<String>,
+    layout: usize,
+    cwd: String,
+    description: String,
+    save: bool,
+    /// Directories matching the cwd after the last Tab
+    completions: Vec<String>,
+    error: Option<String>,
+}
+
+impl Wizard {
+    pub fn new(app: &App) -> Self {
+        let layouts = list_layouts(&app.catalog.layouts_dir());
+        let layout = layouts
+            .iter()
+            .position(|l| l == "new")
+            .or_else(|| layouts.iter().position(|l| l == "default"))
+            .unwrap_or(0);
+        // The picker's directory, which honours --cwd
+        let cwd = catalog::contract_home(&app.dir);
+
+        Self {
+            field: Field::Name,
+            name: String::new(),
+            layouts,
+            layout,
+            cwd,
+            description: String::new(),
+            save: false,
+            completions: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn move_field(&mut self, forward: bool) {
+        let i = FIELDS.iter().position(|&f| f == self.field).unwrap_or(0);
+        let n = FIELDS.len();
+        self.field = FIELDS[if forward { (i + 1) % n } else { (i + n - 1) % n }];
+        self.completions.clear();
+    }
+
+    fn input(&mut self) -> Option<&mut String> {
+        match self.field {
+            Field::Name => Some(&mut self.name),
+            Field::Cwd => Some(&mut self.cwd),
+            Field::Description => Some(&mut self.description),
+            Field::Layout | Field::Save => None,
+        }
+    }
+
+    fn placeholder_name() -> String {
+        format!("new-{}", Local::now().format("%Y%m%d-%H%M%S"))
+    }
+
+    /// Checks the form and turns it into a launch, saving the catalog entry
+    /// if asked to.
+    fn submit(&self, app: &App) -> Result<Picked, String> {
+        let name = if self.name.is_empty() {
+            Self::placeholder_name()
+        } else {
+            self.name.clone()
+        };
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) {
+            return Err("Names may only contain letters, digits, -, _ and .".to_string());
+        }
+        if app.running.iter().any(|r| r.name == name) {
+            return Err(format!("A session named {} already exists", name));
+        }
+        let Some(layout) = self.layouts.get(self.layout) else {
+            return Err(format!("No layouts in {}", app.catalog.layouts_dir().display()));
+        };
+        let cwd = self.cwd.trim_end_matches('/');
+        if !cwd.is_empty() && !catalog::expand_home(cwd).is_dir() {
+            return Err(format!("{} is not a directory", cwd));
+        }
+
+        let mut instance = name.clone();
+        if self.save {
+            if self.name.is_empty() {
+                return Err("Name the entry to save it".to_string());
+            }
+            if app.catalog.find(&name).is_some() {
+                return Err(format!("The catalog already has an entry named {}", name));
+            }
+            let mut session = Session::new(&name, &self.description);
+            if *layout != name {
+                session.layout = Some(layout.clone());
+            }
+            session.cwd = (!cwd.is_empty()).then(|| cwd.to_string());
+            catalog::append_session(&app.catalog, &session).map_err(|e| format!("{:#}", e))?;
+            // Named like the entry's other instances, so it belongs to it
+            instance = naming::instance_name(&app.catalog, &session, &app.running).map_err(|e| format!("{:#}", e))?;
+        }
+
+        Ok(Picked::New {
+            name: instance,
+            layout: layout.clone(),
+            cwd: (!cwd.is_empty()).then(|| catalog::expand_home(cwd)),
+        })
+    }
+
+    /// Completes the cwd to the longest common prefix of the directories it
+    /// could name.
+    fn complete_cwd(&mut self) {
+        let (dir, prefix) = match self.cwd.rfind('/') {
+            Some(i) => (self.cwd[..=i].to_string(), self.cwd[i + 1..].to_string()),
+            None => (String::new(), self.cwd.clone()),
+        };
+        let base = if dir.is_empty() { PathBuf::from(".") } else { catalog::expand_home(&dir) };
+
+        let mut matches: Vec<String> = std::fs::read_dir(&base)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|e| e.path().is_dir())
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .filter(|name| name.starts_with(&prefix))
+                    // Hidden directories only when asked for
+                    .filter(|name| prefix.starts_with('.') || !name.starts_with('.'))
+                    .collect()
+            })
+            .unwrap_or_default();
+        matches.sort();
+
+        match matches.as_slice() {
+            [] => self.completions.clear(),
+            [only] => {
+                self.cwd = format!("{}{}/", dir, only);
+                self.completions.clear();
+            }
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.clone(), |common, m| {
+                    common
+                        .chars()
+                        .zip(m.chars())
+                        .take_while(|(a, b)| a == b)
+                        .map(|(a, _)| a)
+                        .collect()
+                });
+                self.cwd = format!("{}{}", dir, common);
+                self.completions = matches;
+            }
+        }
+    }
+}
+
+/// Layout names in `dir`, without swap layouts.
+fn list_layouts(dir: &Path) -> Vec<String> {
+    let mut layouts: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    let name = e.file_name().to_string_lossy().into_owned();
+                    let stem = name.strip_suffix(".kdl")?;
+                    (!stem.ends_with(".swap")).then(|| stem.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    layouts.sort();
+    layouts
+}
+
+/// Handles a key while the form is open; returns the launch once submitted.
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Option<Picked> {
+    let mut wizard = app.wizard.take()?;
+    wizard.error = None;
+
+    match key.code {
+        KeyCode::Esc => return None,
+        KeyCode::Enter => match wizard.submit(app) {
+            Ok(picked) => return Some(picked),
+            Err(e) => wizard.error = Some(e),
+        },
+        KeyCode::Down => wizard.move_field(true),
+        KeyCode::Up | KeyCode::BackTab => wizard.move_field(false),
+        KeyCode::Tab if wizard.field == Field::Cwd => wizard.complete_cwd(),
+        KeyCode::Tab => wizard.move_field(true),
+        KeyCode::Left | KeyCode::Right if wizard.field == Field::Layout && !wizard.layouts.is_empty() => {
+            let n = wizard.layouts.len();
+            wizard.layout = if key.code == KeyCode::Right {
+                (wizard.layout + 1) % n
+            } else {
+                (wizard.layout + n - 1) % n
+            };
+        }
+        KeyCode::Char(' ') if wizard.field == Field::Save => wizard.save = !wizard.save,
+        KeyCode::Backspace => {
+            if let Some(input) = wizard.input() {
+                input.pop();
+            }
+            wizard.completions.clear();
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            let field = wizard.field;
+            if let Some(input) = wizard.input() {
+                // Session names can't contain whitespace or slashes
+                if field != Field::Name || (!c.is_whitespace() && c != '/') {
+                    input.push(c);
+                }
+            }
+            wizard.completions.clear();
+        }
+        _ => {}
+    }
+
+    app.wizard = Some(wizard);
+    None
+}
+
+pub fn render(f: &mut Frame, wizard: &Wizard, area: Rect) {
+    let key = Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::DarkGray);
+
+    let row = |field: Field, label: &'static str, value: Span<'static>| {
+        let focused = wizard.field == field;
+        let marker = if focused { "▶ " } else { "  " };
+        let label_style = if focused { key } else { dim };
+        let mut spans = vec![
+            Span::styled(marker, key),
+            Span::styled(format!("{:<12}", label), label_style),
+            value,
+        ];
+        if focused && !matches!(field, Field::Layout | Field::Save) {
+            spans.push(Span::styled("_", Style::default().fg(Color::Blue)));
+        }
+        Line::from(spans)
+    };
+    let text = |s: &str, placeholder: String| {
+        if s.is_empty() {
+            Span::styled(placeholder, dim)
+        } else {
+            Span::styled(s.to_string(), Style::default().fg(Color::White))
+        }
+    };
+
+    let layout = match wizard.layouts.get(wizard.layout) {
+        Some(layout) => Span::styled(format!("◀ {} ▶", layout), Style::default().fg(Color::White)),
+        None => Span::styled("no layouts found", Style::default().fg(Color::Red)),
+    };
+
+    let mut lines = vec![
+        row(Field::Name, "Name", text(&wizard.name, Wizard::placeholder_name())),
+        row(Field::Layout, "Layout", layout),
+        row(Field::Cwd, "Directory", text(&wizard.cwd, "zellij's default".to_string())),
+    ];
+    for completion in wizard.completions.iter().take(MAX_COMPLETIONS) {
+        lines.push(Line::from(Span::styled(format!("              {}/", completion), dim)));
+    }
+    if wizard.completions.len() > MAX_COMPLETIONS {
+        lines.push(Line::from(Span::styled(
+            format!("              … {} more", wizard.completions.len() - MAX_COMPLETIONS),
+            dim,
+        )));
+    }
+    // Only kept in the saved entry, so dimmed while saving is off
+    let description = match text(&wizard.description, "optional, for saved entries".to_string()) {
+        value if !wizard.save => value.style(dim),
+        value => value,
+    };
+    lines.push(row(Field::Description, "Description", description));
+    lines.push(row(
+        Field::Save,
+        "Save entry",
+        Span::styled(
+            if wizard.save { "[x] add to catalog.toml" } else { "[ ] add to catalog.toml" },
+            Style::default().fg(Color::White),
+        ),
+    ));
+    lines.push(Line::from(""));
+    if let Some(error) = &wizard.error {
+        lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::Red))));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("⏎", key),
+        Span::styled(" create  ", dim),
+        Span::styled("↑↓", key),
+        Span::styled(" field  ", dim),
+        Span::styled("←→", key),
+        Span::styled(" layout  ", dim),
+        Span::styled("tab", key),
+        Span::styled(" complete  ", dim),
+        Span::styled("space", key),
+        Span::styled(" toggle  ", dim),
+        Span::styled("esc", key),
+        Span::styled(" cancel", dim),
+    ]));
+
+    let width = lines
+        .iter()
+        .map(|l| l.width() as u16)
+        .max()
+        .unwrap_or(0)
+        .saturating_add(4)
+        .max(50)
+        .min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    f.render_widget(Clear, popup);
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Blue))
+                .title(" New session ")
+                .title_style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+        ),
+        popup,
+    );
+}
@@ -0,0 +1,103 @@
+//! Entries with `worktrees = true` get an entry per further git worktree of
+//! their `cwd` (from `git worktree list`), listed right below them. Each
+//! launches the entry's layout in the worktree, as a single instance named
+//! after the worktree's branch, e.g. `colony-feature-login`.
+
+use crate::catalog::{self, Catalog, Session};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+struct Worktree {
+    path: PathBuf,
+    /// Short branch name, `None` on a detached HEAD
+    branch: Option<String>,
+}
+
+/// Inserts the worktree entries after the entries they belong to.
+pub fn expand(catalog: &mut Catalog) {
+    let mut i = 0;
+    while i < catalog.sessions.len() {
+        let parent = &catalog.sessions[i];
+        let Some(cwd) = parent.cwd.as_deref().filter(|_| parent.worktrees) else {
+            i += 1;
+            continue;
+        };
+        let main = catalog::expand_home(cwd.trim_end_matches('/'));
+        // git reports resolved paths, the catalog may go through symlinks
+        let real = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let main_real = real(&main);
+
+        let mut entries = Vec::new();
+        for worktree in list(&main).into_iter().filter(|w| real(&w.path) != main_real) {
+            let dir_name = || worktree.path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let (label, description) = match &worktree.branch {
+                Some(branch) => (branch.clone(), format!("{} worktree on {}", parent.name, branch)),
+                None => (dir_name(), format!("{} worktree in {} (detached)", parent.name, dir_name())),
+            };
+            let name: String = format!("{}-{}", parent.name, label)
+                .chars()
+                .map(|c| if c.is_whitespace() || c == '/' { '-' } else { c })
+                .collect();
+            if catalog.find(&name).is_some() || entries.iter().any(|e: &Session| e.name == name) {
+                continue;
+            }
+
+            let mut session = Session::new(&name, description);
+            session.layout = Some(parent.layout_name().to_string());
+            session.cwd = Some(catalog::contract_home(&worktree.path));
+            session.notes = parent.notes.clone();
+            session.vars = parent.vars.clone();
+            session.env = parent.env.clone();
+            session.env_source = parent.env_source.clone();
+            session.pre_launch = parent.pre_launch.clone();
+            session.post_launch = parent.post_launch.clone();
+            // One instance per worktree, called like the entry itself
+            session.naming = Some("{name}".to_string());
+            session.worktree_of = Some(parent.name.clone());
+            entries.push(session);
+        }
+
+        let count = entries.len();
+        let names = entries.iter().map(|e| e.name.clone());
+        catalog.sessions[i].worktree_instances.extend(names);
+        catalog.sessions.splice(i + 1..i + 1, entries);
+        i += count + 1;
+    }
+}
+
+/// The worktrees of the repository at `dir`, the main one first.
+fn list(dir: &Path) -> Vec<Worktree> {
+    let Ok(output) = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["worktree", "list", "--porcelain"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    let mut worktrees = Vec::new();
+    // Records are separated by blank lines
+    for record in String::from_utf8_lossy(&output.stdout).split("\n\n") {
+        let mut path = None;
+        let mut branch = None;
+        let mut usable = true;
+        for line in record.lines() {
+            if let Some(p) = line.strip_prefix("worktree ") {
+                path = Some(PathBuf::from(p));
+            } else if let Some(b) = line.strip_prefix("branch ") {
+                branch = Some(b.strip_prefix("refs/heads/").unwrap_or(b).to_string());
+            } else if line == "bare" || line.starts_with("prunable") {
+                // Nothing to work in, or the directory is gone
+                usable = false;
+            }
+        }
+        if let Some(path) = path.filter(|_| usable) {
+            worktrees.push(Worktree { path, branch });
+        }
+    }
+    worktrees
+}
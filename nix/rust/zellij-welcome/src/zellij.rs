@@ -0,0 +1,265 @@
+//! Helpers around the `zellij` CLI.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+pub struct RunningSession {
+    pub name: String,
+    pub age: Option<Duration>,
+    pub exited: bool,
+    pub current: bool,
+}
+
+/// Lists all sessions known to zellij, including exited ones.
+/// Returns an empty list if zellij is unavailable or has no sessions.
+pub fn list_sessions() -> Vec<RunningSession> {
+    let output = match Command::new("zellij").args(["list-sessions"]).output() {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+
+    parse_sessions(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parses `zellij list-sessions` output, e.g.
+/// `colony-20260203-101500 [Created 2h 3m 10s ago] (current)`.
+pub fn parse_sessions(output: &str) -> Vec<RunningSession> {
+    output
+        .lines()
+        .map(strip_ansi_codes)
+        .filter_map(|line| {
+            let name = line.split_whitespace().next()?.to_string();
+            let age = line
+                .split_once("[Created ")
+                .and_then(|(_, rest)| rest.split_once(" ago]"))
+                .and_then(|(age, _)| parse_age(age));
+
+            Some(RunningSession {
+                name,
+                age,
+                exited: line.contains("EXITED"),
+                current: line.contains("(current)"),
+            })
+        })
+        .collect()
+}
+
+pub fn kill_session(name: &str) -> Result<()> {
+    run(&["kill-session", name])
+}
+
+/// Deletes an exited session's resurrection data; `force` also kills it
+/// first if it is still running.
+pub fn delete_session(name: &str, force: bool) -> Result<()> {
+    if force {
+        run(&["delete-session", "--force", name])
+    } else {
+        run(&["delete-session", name])
+    }
+}
+
+pub fn rename_session(name: &str, new_name: &str) -> Result<()> {
+    run(&["--session", name, "action", "rename-session", new_name])
+}
+
+/// Pids of the clients attached to session `name`. zellij's CLI can't list
+/// them, so they're found as the peers of the server's socket with `ss`
+/// (Linux only).
+pub fn clients(name: &str) -> Result<Vec<u32>> {
+    let output = Command::new("ss")
+        .args(["-xpn"])
+        .output()
+        .context("Failed to run ss")?;
+    if !output.status.success() {
+        bail!("ss -xpn failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(parse_clients(&String::from_utf8_lossy(&output.stdout), name))
+}
+
+/// Parses `ss -xpn` output for the clients of session `name`. The server's
+/// end of each connection is named after the socket, e.g.
+/// `u_str ESTAB 0 0 /run/user/1000/zellij/0.41.2/colony 4821 * 4822 users:((…))`,
+/// and the client's end is the line whose own inode is that peer.
+fn parse_clients(output: &str, name: &str) -> Vec<u32> {
+    let connections: Vec<Vec<&str>> = output.lines().map(|l| l.split_whitespace().collect()).collect();
+    let peers: Vec<&str> = connections
+        .iter()
+        .filter(|fields| {
+            fields.get(4).is_some_and(|socket| {
+                let socket = Path::new(socket);
+                socket.file_name().is_some_and(|n| n == name)
+                    && socket.iter().any(|c| c.to_string_lossy().starts_with("zellij"))
+            })
+        })
+        .filter_map(|fields| fields.get(7).copied())
+        .collect();
+
+    let mut pids: Vec<u32> = connections
+        .iter()
+        .filter(|fields| fields.get(5).is_some_and(|inode| peers.contains(inode)))
+        // `users:(("zellij",pid=4711,fd=5))`, one entry per descriptor
+        .flat_map(|fields| fields.iter().skip(8).flat_map(|users| users.split("pid=").skip(1)))
+        .filter_map(|rest| rest.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok())
+        .collect();
+    pids.sort_unstable();
+    pids.dedup();
+    pids
+}
+
+/// Creates session `name` with `layout` (a name or a path) without attaching
+/// to it, started in `cwd` if given and with `env` exported.
+pub fn create_background(name: &str, layout: &str, cwd: Option<&Path>, env: &[(String, String)]) -> Result<()> {
+    let mut command = Command::new("zellij");
+    command.args(["attach", "--create-background", name, "options", "--default-layout", layout]);
+    if let Some(cwd) = cwd {
+        command.current_dir(cwd);
+    }
+    command.envs(env.iter().map(|(k, v)| (k, v)));
+    run_command(command, &["attach", "--create-background", name])
+}
+
+/// Resurrects exited session `name` without attaching to it.
+pub fn resurrect_background(name: &str) -> Result<()> {
+    run(&["attach", "--create-background", name])
+}
+
+fn run(args: &[&str]) -> Result<()> {
+    let mut command = Command::new("zellij");
+    command.args(args);
+    run_command(command, args)
+}
+
+/// Runs a zellij command, failing with its stderr; `args` name it in errors.
+fn run_command(mut command: Command, args: &[&str]) -> Result<()> {
+    let output = command.output().context("Failed to run zellij")?;
+
+    if !output.status.success() {
+        bail!(
+            "zellij {} failed: {}",
+            args.join(" "),
+            strip_ansi_codes(String::from_utf8_lossy(&output.stderr).trim())
+        );
+    }
+    Ok(())
+}
+
+/// Parses humantime-style durations as printed by zellij, e.g. `1day 2h 3m 4s`.
+fn parse_age(s: &str) -> Option<Duration> {
+    let mut total = 0u64;
+    for part in s.split_whitespace() {
+        let split = part.find(|c: char| !c.is_ascii_digit())?;
+        let (num, unit) = part.split_at(split);
+        let n: u64 = num.parse().ok()?;
+        let secs = match unit {
+            "y" | "year" | "years" => 365 * 86_400,
+            "M" | "month" | "months" => 30 * 86_400,
+            "w" | "week" | "weeks" => 7 * 86_400,
+            "d" | "day" | "days" => 86_400,
+            "h" | "hour" | "hours" => 3_600,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "ms" | "us" | "ns" => 0,
+            _ => return None,
+        };
+        total += n * secs;
+    }
+    Some(Duration::from_secs(total))
+}
+
+/// Compact age for display, keeping the two most significant units.
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let (d, h, m, s) = (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60, secs % 60);
+    if d > 0 {
+        format!("{}d {}h", d, h)
+    } else if h > 0 {
+        format!("{}h {}m", h, m)
+    } else if m > 0 {
+        format!("{}m {}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+pub fn strip_ansi_codes(s: &str) -> String {
+    let mut result = String::new();
+    let mut in_escape = false;
+
+    for ch in s.chars() {
+        if ch == '\x1b' {
+            in_escape = true;
+        } else if in_escape {
+            if ch == 'm' {
+                in_escape = false;
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages() {
+        assert_eq!(parse_age("5s"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_age("2h 13m 4s"), Some(Duration::from_secs(2 * 3600 + 13 * 60 + 4)));
+        assert_eq!(parse_age("1day 3hours"), Some(Duration::from_secs(86_400 + 3 * 3600)));
+        assert_eq!(parse_age("1w 2d"), Some(Duration::from_secs(9 * 86_400)));
+        assert_eq!(parse_age("4m 12ms"), Some(Duration::from_secs(240)));
+        assert_eq!(parse_age("3 fortnights"), None);
+        assert_eq!(parse_age("m"), None);
+    }
+
+    #[test]
+    fn age_formats() {
+        assert_eq!(format_age(Duration::from_secs(42)), "42s");
+        assert_eq!(format_age(Duration::from_secs(125)), "2m 5s");
+        assert_eq!(format_age(Duration::from_secs(3 * 3600 + 60)), "3h 1m");
+        assert_eq!(format_age(Duration::from_secs(2 * 86_400 + 5 * 3600 + 59)), "2d 5h");
+    }
+
+    #[test]
+    fn sessions() {
+        let output = "\
+\x1b[32;1mcolony-1\x1b[m [Created \x1b[35;1m2h 13m 4s\x1b[m ago] \x1b[32;1m(current)\x1b[m
+\x1b[32;1mscratch\x1b[m [Created \x1b[35;1m1day\x1b[m ago] (\x1b[31;1mEXITED\x1b[m - attach to resurrect)
+bare
+";
+        let sessions = parse_sessions(output);
+        let summary: Vec<_> = sessions.iter().map(|s| (s.name.as_str(), s.age, s.exited, s.current)).collect();
+        assert_eq!(
+            summary,
+            [
+                ("colony-1", Some(Duration::from_secs(2 * 3600 + 13 * 60 + 4)), false, true),
+                ("scratch", Some(Duration::from_secs(86_400)), true, false),
+                ("bare", None, false, false),
+            ]
+        );
+        assert!(parse_sessions("\n").is_empty());
+    }
+
+    #[test]
+    fn clients_are_the_peers_of_the_server_socket() {
+        let output = "\
+Netid State Recv-Q Send-Q Local Address:Port Peer Address:Port Process
+u_str ESTAB 0 0 /run/user/1000/zellij/0.41.2/colony 4821 * 4822 users:((\"zellij\",pid=100,fd=9))
+u_str ESTAB 0 0 /run/user/1000/zellij/0.41.2/colony 4831 * 4832 users:((\"zellij\",pid=100,fd=10))
+u_str ESTAB 0 0 /run/user/1000/zellij/0.41.2/other 4841 * 4842 users:((\"zellij\",pid=200,fd=9))
+u_str ESTAB 0 0 /tmp/elsewhere/colony 4851 * 4852 users:((\"nc\",pid=300,fd=3))
+u_str ESTAB 0 0 * 4822 * 4821 users:((\"zellij\",pid=4711,fd=5))
+u_str ESTAB 0 0 * 4832 * 4831 users:((\"zellij\",pid=4712,fd=5),(\"zellij\",pid=4712,fd=6))
+u_str ESTAB 0 0 * 4842 * 4841 users:((\"zellij\",pid=4713,fd=5))
+u_str ESTAB 0 0 * 4852 * 4851 users:((\"nc\",pid=4714,fd=5))
+";
+        assert_eq!(parse_clients(output, "colony"), [4711, 4712]);
+        assert_eq!(parse_clients(output, "other"), [4713]);
+        assert!(parse_clients(output, "missing").is_empty());
+    }
+}